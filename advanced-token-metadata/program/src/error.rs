@@ -0,0 +1,53 @@
+//! Program-specific error types
+
+use {solana_program::program_error::ProgramError, thiserror::Error};
+
+/// Errors that may be returned by the advanced token metadata program.
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum AdvancedTokenMetadataError {
+    /// Error 100: Field has no history configured
+    #[error("Field has no history configured")]
+    FieldHistoryNotFound = 100,
+    /// Error 101: Requested version not found in field history
+    #[error("Requested version not found in field history")]
+    FieldVersionNotFound,
+    /// Error 102: Key is not allowed by the key schema
+    #[error("Key is not allowed by the key schema")]
+    UnknownKey,
+    /// Error 103: Value doesn't match the type declared in the key schema
+    #[error("Value doesn't match the type declared in the key schema")]
+    InvalidValueType,
+    /// Error 104: Key is in the reserved namespace
+    #[error("Key is in the reserved namespace")]
+    ReservedKey,
+    /// Error 105: No field write in progress
+    #[error("No field write in progress")]
+    NoFieldWriteInProgress,
+    /// Error 106: Chunk offset is past the end of the staged data
+    #[error("Chunk offset is past the end of the staged data")]
+    InvalidChunkOffset,
+    /// Error 107: Staged data is not valid UTF-8
+    #[error("Staged data is not valid UTF-8")]
+    InvalidFieldValue,
+    /// Error 108: Proxy account isn't the proxy PDA of the mint
+    #[error("Proxy account isn't the proxy PDA of the mint")]
+    IncorrectProxyAuthority,
+    /// Error 109: Metadata account isn't the metadata PDA of the subject
+    #[error("Metadata account isn't the metadata PDA of the subject")]
+    IncorrectSubjectMetadata,
+    /// Error 110: Mint's metadata pointer doesn't point to the metadata account
+    #[error("Mint's metadata pointer doesn't point to the metadata account")]
+    MetadataPointerMismatch,
+    /// Error 111: Metadata is sealed, only field authorities can update it
+    #[error("Metadata is sealed, only field authorities can update it")]
+    MetadataSealed,
+    /// Error 112: Another authority has a field write in progress
+    #[error("Another authority has a field write in progress")]
+    FieldWriteInProgress,
+}
+
+impl From<AdvancedTokenMetadataError> for ProgramError {
+    fn from(e: AdvancedTokenMetadataError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -1,14 +1,22 @@
 //! Implementation of the field authority interface
 
 use {
-    crate::processor::check_update_authority,
+    crate::{
+        processor::{
+            check_update_authority, get_optional_variable_len_value, update_field_tracked,
+        },
+        schema::normalize_writable_field,
+        seal::check_not_sealed,
+        state::ProxyAuthority,
+    },
     borsh::BorshSerialize, // Needed for .serialize() method below to be in scope
     field_authority_interface::{
         errors::FieldAuthorityError,
-        field_to_seed_str,
+        field_to_seed, find_field_pda,
         instructions::{AddFieldAuthority, RemoveFieldAuthority, UpdateFieldWithFieldAuthority},
+        resolve_field_pda_seed,
         state::FieldAuthorityAccount,
-        FIELD_AUTHORITY_PDA_SEED,
+        FieldSeedVersion, FIELD_AUTHORITY_PDA_SEED,
     },
     solana_program::{
         account_info::{next_account_info, AccountInfo},
@@ -20,23 +28,69 @@ use {
         system_instruction, system_program,
         sysvar::{rent::Rent, Sysvar},
     },
-    spl_token_metadata_interface::state::TokenMetadata,
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
     spl_type_length_value::state::{
         realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed,
     },
 };
 
-/// Checks the metadata update authority is correct and signing
+/// Checks the metadata update authority is correct and signing, and the metadata isn't sealed.
+/// For a proxy account, this is the update authority the proxy took over from.
 pub fn check_metadata_update_authority(
     metadata_info: &AccountInfo,
     update_authority_info: &AccountInfo,
 ) -> Result<(), ProgramError> {
-    let token_metadata = {
-        let buffer = metadata_info.try_borrow_data()?;
-        let state = TlvStateBorrowed::unpack(&buffer)?;
-        state.get_first_variable_len_value::<TokenMetadata>()?
-    };
-    check_update_authority(update_authority_info, &token_metadata.update_authority)?;
+    if let Some(proxy_authority) = get_optional_variable_len_value::<ProxyAuthority>(metadata_info)?
+    {
+        let update_authority = OptionalNonZeroPubkey::try_from(proxy_authority.update_authority)?;
+        check_update_authority(update_authority_info, &update_authority)?;
+    } else {
+        let token_metadata = {
+            let buffer = metadata_info.try_borrow_data()?;
+            let state = TlvStateBorrowed::unpack(&buffer)?;
+            state.get_first_variable_len_value::<TokenMetadata>()?
+        };
+        check_update_authority(update_authority_info, &token_metadata.update_authority)?;
+    }
+    check_not_sealed(metadata_info)?;
+    Ok(())
+}
+
+/// Checks the field PDA of a field and returns its field seed and bump. Fields use raw seeds
+/// whenever they fit, so PDAs created before hashed seeds still resolve.
+pub fn check_field_pda(
+    program_id: &Pubkey,
+    metadata_info: &AccountInfo,
+    field_pda_info: &AccountInfo,
+    field: &Field,
+) -> Result<(Vec<u8>, u8), ProgramError> {
+    let version = FieldSeedVersion::for_field(field);
+    let field_seed =
+        field_to_seed(field.clone(), version).ok_or(FieldAuthorityError::IncorrectFieldPda)?;
+    let (field_pda, bump) = find_field_pda(program_id, metadata_info.key, field.clone(), version)
+        .ok_or(FieldAuthorityError::IncorrectFieldPda)?;
+    if *field_pda_info.key != field_pda {
+        return Err(FieldAuthorityError::IncorrectFieldPda.into());
+    }
+    Ok((field_seed, bump))
+}
+
+/// Checks the field PDA of an existing field authority, given the field as written. Field PDAs
+/// created before keys were normalized are derived from the key as written, and still resolve.
+pub fn check_existing_field_pda(
+    program_id: &Pubkey,
+    metadata_info: &AccountInfo,
+    field_pda_info: &AccountInfo,
+    field: &Field,
+) -> ProgramResult {
+    resolve_field_pda_seed(
+        program_id,
+        metadata_info.key,
+        field.clone(),
+        field_pda_info.key,
+    )
+    .ok_or(FieldAuthorityError::IncorrectFieldPda)?;
     Ok(())
 }
 
@@ -44,8 +98,10 @@ pub fn check_metadata_update_authority(
 pub fn process_add_field_authority(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: AddFieldAuthority,
+    mut data: AddFieldAuthority,
 ) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
     let account_info_iter = &mut accounts.iter();
     let payer_info = next_account_info(account_info_iter)?;
     let metadata_info = next_account_info(account_info_iter)?;
@@ -54,16 +110,8 @@ pub fn process_add_field_authority(
     let system_program_info = next_account_info(account_info_iter)?;
 
     // Check PDA
-    let field_seed_str = field_to_seed_str(data.field);
-    let field_pda_seeds = [
-        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
-        metadata_info.key.as_ref(),
-    ];
-    let (field_pda, bump) = Pubkey::find_program_address(&field_pda_seeds, program_id);
-    if *field_pda_info.key != field_pda {
-        return Err(FieldAuthorityError::IncorrectFieldPda.into());
-    }
+    let (field_seed, bump) =
+        check_field_pda(program_id, metadata_info, field_pda_info, &data.field)?;
 
     check_metadata_update_authority(metadata_info, update_authority_info)?;
 
@@ -88,7 +136,7 @@ pub fn process_add_field_authority(
     ];
     let field_pda_seeds_with_bump = [
         FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
+        &field_seed,
         metadata_info.key.as_ref(),
         &[bump],
     ];
@@ -106,10 +154,13 @@ pub fn process_add_field_authority(
 
 /// Proccesses an UpdateFieldWithFieldAuthority instruction
 pub fn process_update_field_with_field_authority(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: UpdateFieldWithFieldAuthority,
+    mut data: UpdateFieldWithFieldAuthority,
 ) -> ProgramResult {
+    let field_as_written = data.field.clone();
+    data.field = normalize_writable_field(data.field)?;
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let field_authority_info = next_account_info(account_info_iter)?;
@@ -122,15 +173,22 @@ pub fn process_update_field_with_field_authority(
         state.get_first_variable_len_value::<TokenMetadata>()?
     };
 
-    // Check field authority
+    // Check PDA
+    check_existing_field_pda(program_id, metadata_info, field_pda_info, &field_as_written)?;
+
+    // Check field authority, the PDA may be derived correctly but not initialized
     let field_pda_data =
-        try_from_slice_unchecked::<FieldAuthorityAccount>(&field_pda_info.data.borrow()).unwrap();
+        try_from_slice_unchecked::<FieldAuthorityAccount>(&field_pda_info.data.borrow())
+            .map_err(|_| FieldAuthorityError::IncorrectFieldPda)?;
     if field_pda_data.authority != *field_authority_info.key {
         return Err(FieldAuthorityError::IncorrectFieldPda.into());
     }
+    if !field_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
 
     // Update the field
-    token_metadata.update(data.field, data.value);
+    update_field_tracked(metadata_info, &mut token_metadata, data.field, data.value)?;
 
     // Update / realloc the account
     realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
@@ -150,16 +208,8 @@ pub fn process_remove_field_authority(
     let field_pda_info = next_account_info(account_info_iter)?;
 
     // Check PDA
-    let field_seed_str = field_to_seed_str(data.field);
-    let seeds = [
-        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
-        metadata_info.key.as_ref(),
-    ];
-    let (pda, _bump) = Pubkey::find_program_address(&seeds, program_id);
-    if *field_pda_info.key != pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    check_existing_field_pda(program_id, metadata_info, field_pda_info, &data.field)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
 
     check_metadata_update_authority(metadata_info, update_authority_info)?;
 
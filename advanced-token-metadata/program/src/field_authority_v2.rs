@@ -1,13 +1,22 @@
 //! Implementation of the field authority interface v2
 
 use {
-    crate::field_authority::check_metadata_update_authority,
+    crate::{
+        field_authority::check_metadata_update_authority,
+        processor::{
+            alloc_and_pack_variable_len_entry, check_update_authority,
+            get_optional_variable_len_value, update_field_tracked,
+        },
+        schema::normalize_writable_field,
+        seal::check_not_sealed,
+    },
     field_authority_interface::{
         errors::FieldAuthorityError,
         instructions_v2::{
             AddFieldAuthorityV2, InitializeFieldAuthorities, RemoveFieldAuthorityV2,
-            UpdateFieldWithFieldAuthorityV2,
+            RemoveKeyWithFieldAuthorityV2, UpdateFieldWithFieldAuthorityV2,
         },
+        normalize_field, normalize_key,
         state::{FieldAuthorities, FieldAuthority},
     },
     solana_program::{
@@ -15,20 +24,62 @@ use {
         entrypoint::ProgramResult,
         program_error::ProgramError,
         pubkey::Pubkey,
+        rent::Rent,
+        sysvar::Sysvar,
+    },
+    spl_token_metadata_interface::{
+        error::TokenMetadataError,
+        state::{Field, TokenMetadata},
     },
-    spl_token_metadata_interface::state::TokenMetadata,
     // TlvState needed for get_first_variable_len_value()
     spl_type_length_value::state::{
-        realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed, TlvStateMut,
+        realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed,
     },
 };
 
+/// Checks the signer is either the metadata update authority or a field authority of the field.
+/// Once the metadata is sealed, only field authorities pass.
+pub fn check_update_or_field_authority(
+    metadata_info: &AccountInfo,
+    authority_info: &AccountInfo,
+    token_metadata: &TokenMetadata,
+    field: &Field,
+) -> Result<(), ProgramError> {
+    if check_update_authority(authority_info, &token_metadata.update_authority).is_ok()
+        && check_not_sealed(metadata_info).is_ok()
+    {
+        return Ok(());
+    }
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let Some(field_authorities) =
+        get_optional_variable_len_value::<FieldAuthorities>(metadata_info)?
+    else {
+        return Err(FieldAuthorityError::IncorrectFieldAuthority.into());
+    };
+    let field_authority = FieldAuthority {
+        field: field.clone(),
+        authority: *authority_info.key,
+    };
+    if !field_authorities.contains_field_authority(field_authority) {
+        return Err(FieldAuthorityError::IncorrectFieldAuthority.into());
+    }
+
+    Ok(())
+}
+
 /// Proccesses an InitializeFieldAuthorities instruction
 pub fn process_initialize_field_authorities(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: InitializeFieldAuthorities,
+    mut data: InitializeFieldAuthorities,
 ) -> ProgramResult {
+    for field_authority in data.authorities.iter_mut() {
+        field_authority.field = normalize_writable_field(field_authority.field.clone())?;
+    }
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let update_authority_info = next_account_info(account_info_iter)?;
@@ -40,12 +91,8 @@ pub fn process_initialize_field_authorities(
         authorities: data.authorities,
     };
 
-    // Allocate a TLV entry for the space and write it in
-    let mut buffer = metadata_info.try_borrow_mut_data()?;
-    let mut state = TlvStateMut::unpack(&mut buffer)?;
-    // TODO: Perhaps could use realloc here to not require the initial account creation? We'll
-    // keep alloc for now to match TokenMetadata
-    state.alloc_and_pack_variable_len_entry(&field_authorities, false)?;
+    // Allocate a TLV entry, reallocating if the account wasn't created with room for it
+    alloc_and_pack_variable_len_entry(metadata_info, &field_authorities)?;
 
     Ok(())
 }
@@ -54,8 +101,10 @@ pub fn process_initialize_field_authorities(
 pub fn process_add_field_authority_v2(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: AddFieldAuthorityV2,
+    mut data: AddFieldAuthorityV2,
 ) -> ProgramResult {
+    data.field_authority.field = normalize_writable_field(data.field_authority.field)?;
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let update_authority_info = next_account_info(account_info_iter)?;
@@ -85,8 +134,10 @@ pub fn process_add_field_authority_v2(
 pub fn process_update_field_with_field_authority_v2(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: UpdateFieldWithFieldAuthorityV2,
+    mut data: UpdateFieldWithFieldAuthorityV2,
 ) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let field_authority_info = next_account_info(account_info_iter)?;
@@ -116,7 +167,7 @@ pub fn process_update_field_with_field_authority_v2(
     };
 
     // Update the field
-    token_metadata.update(data.field, data.value);
+    update_field_tracked(metadata_info, &mut token_metadata, data.field, data.value)?;
 
     // Update / realloc the account
     realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
@@ -124,12 +175,84 @@ pub fn process_update_field_with_field_authority_v2(
     Ok(())
 }
 
+/// Proccesses a RemoveKeyWithFieldAuthorityV2 instruction
+pub fn process_remove_key_with_field_authority_v2(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: RemoveKeyWithFieldAuthorityV2,
+) -> ProgramResult {
+    let key = normalize_key(&data.key);
+    let field = normalize_writable_field(Field::Key(data.key))?;
+
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let field_authority_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+
+    // Check field authority
+    let field_authorities = {
+        let buffer = metadata_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&buffer)?;
+        state.get_first_variable_len_value::<FieldAuthorities>()?
+    };
+    let field_authority = FieldAuthority {
+        field,
+        authority: *field_authority_info.key,
+    };
+    if !field_authorities.contains_field_authority(field_authority) {
+        return Err(FieldAuthorityError::IncorrectFieldAuthority.into());
+    }
+    if !field_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Deserialize the metadata, but scope the data borrow since we'll realloc the account
+    let mut token_metadata = {
+        let buffer = metadata_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&buffer)?;
+        state.get_first_variable_len_value::<TokenMetadata>()?
+    };
+
+    // Keys written before normalization are matched by their normalized form
+    let previous_len = token_metadata.additional_metadata.len();
+    token_metadata
+        .additional_metadata
+        .retain(|(k, _)| normalize_key(k) != key);
+    if token_metadata.additional_metadata.len() == previous_len {
+        if data.idempotent {
+            return Ok(());
+        }
+        return Err(TokenMetadataError::KeyNotFound.into());
+    }
+    let previous_data_len = metadata_info.data_len();
+    realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
+
+    // Refund only the rent freed by the smaller account, other lamports stay with the metadata
+    let rent = Rent::get()?;
+    let freed_lamports = rent
+        .minimum_balance(previous_data_len)
+        .saturating_sub(rent.minimum_balance(metadata_info.data_len()));
+    let refund = freed_lamports.min(
+        metadata_info
+            .lamports()
+            .saturating_sub(rent.minimum_balance(metadata_info.data_len())),
+    );
+    if refund > 0 {
+        **metadata_info.try_borrow_mut_lamports()? -= refund;
+        **destination_info.try_borrow_mut_lamports()? += refund;
+    }
+
+    Ok(())
+}
+
 /// Proccesses an AddFieldAuthorityV2 instruction
 pub fn process_remove_field_authority_v2(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: RemoveFieldAuthorityV2,
+    mut data: RemoveFieldAuthorityV2,
 ) -> ProgramResult {
+    data.field_authority.field = normalize_field(data.field_authority.field);
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let update_authority_info = next_account_info(account_info_iter)?;
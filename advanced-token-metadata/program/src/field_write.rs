@@ -0,0 +1,181 @@
+//! Chunked writes of field values too large for a single transaction
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        field_authority_v2::check_update_or_field_authority,
+        instruction::{AbortFieldWrite, BeginFieldWrite, CommitFieldWrite, WriteFieldChunk},
+        processor::{
+            alloc_and_pack_variable_len_entry, check_update_authority,
+            get_optional_variable_len_value, update_field_tracked,
+        },
+        schema::normalize_writable_field,
+        state::FieldWriteBuffer,
+    },
+    field_authority_interface::errors::FieldAuthorityError,
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        clock::Clock,
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+    spl_token_metadata_interface::state::TokenMetadata,
+    spl_type_length_value::state::{
+        realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed,
+    },
+};
+
+/// Returns the write in progress, checking the signer is the authority that began it
+fn get_field_write_buffer(
+    metadata_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> Result<FieldWriteBuffer, ProgramError> {
+    let field_write_buffer = get_optional_variable_len_value::<FieldWriteBuffer>(metadata_info)?
+        .filter(|buffer| buffer.authority.is_some())
+        .ok_or(AdvancedTokenMetadataError::NoFieldWriteInProgress)?;
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if field_write_buffer.authority != Some(*authority_info.key) {
+        return Err(FieldAuthorityError::IncorrectFieldAuthority.into());
+    }
+    Ok(field_write_buffer)
+}
+
+/// Processes a BeginFieldWrite instruction
+pub fn process_begin_field_write(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: BeginFieldWrite,
+) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let token_metadata = {
+        let buffer = metadata_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&buffer)?;
+        state.get_first_variable_len_value::<TokenMetadata>()?
+    };
+
+    check_update_or_field_authority(metadata_info, authority_info, &token_metadata, &data.field)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut field_write_buffer = FieldWriteBuffer {
+        field: data.field,
+        authority: Some(*authority_info.key),
+        started_at: now,
+        data: vec![],
+    };
+    if let Some(previous) = get_optional_variable_len_value::<FieldWriteBuffer>(metadata_info)? {
+        if previous.is_owned_by_other(authority_info.key, now) {
+            return Err(AdvancedTokenMetadataError::FieldWriteInProgress.into());
+        }
+        // Restarting keeps the start time, so a write can't be held past its expiry
+        if previous.authority == field_write_buffer.authority {
+            field_write_buffer.started_at = previous.started_at;
+        }
+        realloc_and_pack_first_variable_len(metadata_info, &field_write_buffer)?;
+    } else {
+        alloc_and_pack_variable_len_entry(metadata_info, &field_write_buffer)?;
+    }
+
+    Ok(())
+}
+
+/// Processes a WriteFieldChunk instruction
+pub fn process_write_field_chunk(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: WriteFieldChunk,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut field_write_buffer = get_field_write_buffer(metadata_info, authority_info)?;
+    if !field_write_buffer.write(data.offset as usize, &data.bytes) {
+        return Err(AdvancedTokenMetadataError::InvalidChunkOffset.into());
+    }
+    realloc_and_pack_first_variable_len(metadata_info, &field_write_buffer)?;
+
+    Ok(())
+}
+
+/// Processes a CommitFieldWrite instruction
+pub fn process_commit_field_write(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: CommitFieldWrite,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut field_write_buffer = get_field_write_buffer(metadata_info, authority_info)?;
+
+    // Deserialize the metadata, but scope the data borrow since we'll probably realloc the account
+    let mut token_metadata = {
+        let buffer = metadata_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&buffer)?;
+        state.get_first_variable_len_value::<TokenMetadata>()?
+    };
+
+    // The authority may have been removed since the write began
+    let field = field_write_buffer.field.clone();
+    check_update_or_field_authority(metadata_info, authority_info, &token_metadata, &field)?;
+
+    let value = String::from_utf8(std::mem::take(&mut field_write_buffer.data))
+        .map_err(|_| AdvancedTokenMetadataError::InvalidFieldValue)?;
+
+    // Empty the buffer first, so the account doesn't grow by the value twice
+    field_write_buffer.authority = None;
+    realloc_and_pack_first_variable_len(metadata_info, &field_write_buffer)?;
+
+    // Update the field
+    update_field_tracked(metadata_info, &mut token_metadata, field, value)?;
+
+    // Update / realloc the account
+    realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
+
+    Ok(())
+}
+
+/// Processes an AbortFieldWrite instruction
+pub fn process_abort_field_write(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: AbortFieldWrite,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut field_write_buffer =
+        get_optional_variable_len_value::<FieldWriteBuffer>(metadata_info)?
+            .filter(|buffer| buffer.authority.is_some())
+            .ok_or(AdvancedTokenMetadataError::NoFieldWriteInProgress)?;
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // The update authority can discard a write left behind by another authority
+    if field_write_buffer.authority != Some(*authority_info.key) {
+        let token_metadata = {
+            let buffer = metadata_info.try_borrow_data()?;
+            let state = TlvStateBorrowed::unpack(&buffer)?;
+            state.get_first_variable_len_value::<TokenMetadata>()?
+        };
+        check_update_authority(authority_info, &token_metadata.update_authority)?;
+    }
+
+    field_write_buffer.authority = None;
+    field_write_buffer.data = vec![];
+    realloc_and_pack_first_variable_len(metadata_info, &field_write_buffer)?;
+
+    Ok(())
+}
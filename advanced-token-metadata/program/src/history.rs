@@ -0,0 +1,126 @@
+//! Opt-in value history for selected fields
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        field_authority::check_metadata_update_authority,
+        field_authority_v2::check_update_or_field_authority,
+        instruction::{ConfigureFieldHistory, RevertField},
+        processor::{
+            alloc_and_pack_variable_len_entry, get_field_value, get_optional_variable_len_value,
+            update_field_tracked,
+        },
+        schema::normalize_writable_field,
+        state::FieldHistories,
+    },
+    field_authority_interface::normalize_field,
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        pubkey::Pubkey,
+    },
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    spl_type_length_value::state::{
+        realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed,
+    },
+};
+
+/// Records the current value of a field in its history before it's overwritten with
+/// `new_value`. Does nothing if the field isn't tracked or the value doesn't change.
+pub fn record_field_history(
+    metadata_info: &AccountInfo,
+    token_metadata: &TokenMetadata,
+    field: &Field,
+    new_value: &str,
+) -> ProgramResult {
+    let Some(mut field_histories) =
+        get_optional_variable_len_value::<FieldHistories>(metadata_info)?
+    else {
+        return Ok(());
+    };
+    let Some(old_value) = get_field_value(token_metadata, field) else {
+        return Ok(());
+    };
+    if old_value == new_value {
+        return Ok(());
+    }
+
+    if field_histories.record(field, old_value) {
+        realloc_and_pack_first_variable_len(metadata_info, &field_histories)?;
+    }
+
+    Ok(())
+}
+
+/// Processes a ConfigureFieldHistory instruction
+pub fn process_configure_field_history(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: ConfigureFieldHistory,
+) -> ProgramResult {
+    data.field = normalize_field(data.field);
+
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+
+    check_metadata_update_authority(metadata_info, update_authority_info)?;
+
+    match get_optional_variable_len_value::<FieldHistories>(metadata_info)? {
+        Some(mut field_histories) => {
+            field_histories.configure(data.field, data.max_versions);
+            realloc_and_pack_first_variable_len(metadata_info, &field_histories)?;
+        }
+        None => {
+            let mut field_histories = FieldHistories::default();
+            field_histories.configure(data.field, data.max_versions);
+            alloc_and_pack_variable_len_entry(metadata_info, &field_histories)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Processes a RevertField instruction
+pub fn process_revert_field(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: RevertField,
+) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    // Deserialize the metadata, but scope the data borrow since we'll probably realloc the account
+    let mut token_metadata = {
+        let buffer = metadata_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&buffer)?;
+        state.get_first_variable_len_value::<TokenMetadata>()?
+    };
+
+    check_update_or_field_authority(metadata_info, authority_info, &token_metadata, &data.field)?;
+
+    // Take the requested version out of the history, the current value is recorded in its place
+    // by the update
+    let mut field_histories = get_optional_variable_len_value::<FieldHistories>(metadata_info)?
+        .ok_or(AdvancedTokenMetadataError::FieldHistoryNotFound)?;
+    let history = field_histories
+        .get_mut(&data.field)
+        .ok_or(AdvancedTokenMetadataError::FieldHistoryNotFound)?;
+    let version = data.version as usize;
+    if version >= history.values.len() {
+        return Err(AdvancedTokenMetadataError::FieldVersionNotFound.into());
+    }
+    let value = history.values.remove(version);
+    realloc_and_pack_first_variable_len(metadata_info, &field_histories)?;
+
+    // Update the field
+    update_field_tracked(metadata_info, &mut token_metadata, data.field, value)?;
+
+    // Update / realloc the account
+    realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
+
+    Ok(())
+}
@@ -0,0 +1,667 @@
+//! Instructions specific to the advanced token metadata program
+
+use {
+    crate::state::KeySchemaEntry,
+    borsh::{BorshDeserialize, BorshSerialize},
+    field_authority_interface::state::FieldAuthority,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+    spl_discriminator::{discriminator::ArrayDiscriminator, SplDiscriminate},
+    spl_token_metadata_interface::state::Field,
+};
+
+/// Configures how many previous values of a field are kept. Zero stops tracking the field.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:configure_field_history")]
+pub struct ConfigureFieldHistory {
+    /// Field to track
+    pub field: Field,
+    /// Maximum number of previous values kept
+    pub max_versions: u8,
+}
+
+/// Restores a previous value of a field from its history
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:revert_field")]
+pub struct RevertField {
+    /// Field to revert
+    pub field: Field,
+    /// Index into the field history, where 0 is the most recent previous value
+    pub version: u8,
+}
+
+/// Sets the key schema, replacing any existing one. An empty schema allows no additional keys.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:set_key_schema")]
+pub struct SetKeySchema {
+    /// Allowed keys and their value types
+    pub entries: Vec<KeySchemaEntry>,
+}
+
+/// Starts staging a new value for a field. Fails while another authority has a write in
+/// progress, unless it expired.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:begin_field_write")]
+pub struct BeginFieldWrite {
+    /// Field to write
+    pub field: Field,
+}
+
+/// Writes a chunk of the staged value
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:write_field_chunk")]
+pub struct WriteFieldChunk {
+    /// Byte offset of the chunk in the staged value
+    pub offset: u32,
+    /// Chunk bytes
+    pub bytes: Vec<u8>,
+}
+
+/// Swaps the staged value into the field
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:commit_field_write")]
+pub struct CommitFieldWrite;
+
+/// Discards the write in progress
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:abort_field_write")]
+pub struct AbortFieldWrite;
+
+/// Makes the proxy PDA of a mint the update authority of the metadata embedded in the mint
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:initialize_proxy")]
+pub struct InitializeProxy {
+    /// Initial field authorities of the embedded metadata
+    pub authorities: Vec<FieldAuthority>,
+}
+
+/// Initializes metadata like `Initialize`, but only for mints of a token program that point
+/// to the metadata account
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:initialize_strict")]
+pub struct InitializeStrict {
+    /// Longer name of the token
+    pub name: String,
+    /// Shortened symbol of the token
+    pub symbol: String,
+    /// URI pointing to more metadata (image, video, etc.)
+    pub uri: String,
+}
+
+/// Seals the metadata, so only its existing field authorities can update it
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:seal_metadata")]
+pub struct SealMetadata {}
+
+/// Initializes metadata for any subject account, stored in the metadata PDA of the subject
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:initialize_for_subject")]
+pub struct InitializeForSubject {
+    /// Longer name of the subject
+    pub name: String,
+    /// Shortened symbol of the subject
+    pub symbol: String,
+    /// URI pointing to more metadata (image, video, etc.)
+    pub uri: String,
+}
+
+/// Updates a field of the embedded metadata through the proxy
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:update_field_with_proxy")]
+pub struct UpdateFieldWithProxy {
+    /// Field to update
+    pub field: Field,
+    /// Value to write for the field
+    pub value: String,
+}
+
+/// Hands the update authority of the embedded metadata back from the proxy and closes the
+/// proxy account
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:reclaim_proxy_authority")]
+pub struct ReclaimProxyAuthority {
+    /// New update authority of the embedded metadata, none makes it immutable
+    pub new_update_authority: Option<Pubkey>,
+}
+
+/// All instructions specific to the advanced token metadata program
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdvancedTokenMetadataInstruction {
+    /// Configures the history of a field
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Update authority
+    ConfigureFieldHistory(ConfigureFieldHistory),
+    /// Restores a previous value of a field. The current value is recorded in the history, so
+    /// a revert can itself be reverted.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Update authority or field authority of the field
+    RevertField(RevertField),
+    /// Sets the key schema
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Update authority
+    SetKeySchema(SetKeySchema),
+    /// Starts a chunked write of a field
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Update authority or field authority of the field
+    BeginFieldWrite(BeginFieldWrite),
+    /// Writes a chunk of a field value
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Authority that began the write
+    WriteFieldChunk(WriteFieldChunk),
+    /// Commits a chunked write, updating the field with the staged value
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Authority that began the write
+    CommitFieldWrite(CommitFieldWrite),
+    /// Creates the proxy account and hands it the update authority of the embedded metadata
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Proxy PDA
+    ///   1. `[w]` Token-2022 mint storing the metadata
+    ///   2. `[s]` Update authority of the embedded metadata
+    ///   3. `[ws]` Payer
+    ///   4. `[]` System program
+    ///   5. `[]` Token-2022 program
+    InitializeProxy(InitializeProxy),
+    /// Updates a field of the embedded metadata with a CPI to Token-2022, signed by the proxy
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Token-2022 mint storing the metadata
+    ///   1. `[w]` Proxy PDA
+    ///   2. `[s]` Update authority or field authority of the field
+    ///   3. `[]` Token-2022 program
+    UpdateFieldWithProxy(UpdateFieldWithProxy),
+    /// Creates the metadata PDA of a subject and initializes its metadata. The subject signs to
+    /// prove control, e.g. a wallet, or a PDA signed for by its program with a CPI.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata PDA of the subject
+    ///   1. `[]` Update authority
+    ///   2. `[s]` Subject
+    ///   3. `[ws]` Payer
+    ///   4. `[]` System program
+    InitializeForSubject(InitializeForSubject),
+    /// Initializes metadata like `Initialize`, additionally requiring the mint to be owned by
+    /// SPL Token or Token-2022, and Token-2022 mints to have a metadata pointer to the metadata
+    /// account
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata
+    ///   1. `[]` Update authority
+    ///   2. `[]` Mint
+    ///   3. `[s]` Mint authority
+    InitializeStrict(InitializeStrict),
+    /// Permanently seals the metadata. The update authority can no longer update fields, remove
+    /// keys, or manage field authorities, schemas and histories, while the existing field
+    /// authorities keep updating their fields. The update authority can still be handed over.
+    ///
+    /// Works on proxy accounts too. The account must hold enough lamports for the seal entry.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata or proxy account
+    ///   1. `[s]` Update authority
+    SealMetadata(SealMetadata),
+    /// Discards the write in progress, e.g. one left behind by another authority
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Metadata account
+    ///   1. `[s]` Authority that began the write, or update authority
+    AbortFieldWrite(AbortFieldWrite),
+    /// Makes the given key the update authority of the embedded metadata with a CPI to
+    /// Token-2022, signed by the proxy, then closes the proxy account. Only the update authority
+    /// stored in the proxy can reclaim it, and not once the proxy is sealed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[w]` Token-2022 mint storing the metadata
+    ///   1. `[w]` Proxy PDA
+    ///   2. `[s]` Update authority stored in the proxy
+    ///   3. `[w]` Receives the lamports of the proxy account
+    ///   4. `[]` Token-2022 program
+    ReclaimProxyAuthority(ReclaimProxyAuthority),
+}
+
+impl AdvancedTokenMetadataInstruction {
+    /// Unpacks a byte buffer into an
+    /// [AdvancedTokenMetadataInstruction](enum.AdvancedTokenMetadataInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < ArrayDiscriminator::LENGTH {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (discriminator, rest) = input.split_at(ArrayDiscriminator::LENGTH);
+        Ok(match discriminator {
+            ConfigureFieldHistory::SPL_DISCRIMINATOR_SLICE => {
+                let data = ConfigureFieldHistory::try_from_slice(rest)?;
+                Self::ConfigureFieldHistory(data)
+            }
+            RevertField::SPL_DISCRIMINATOR_SLICE => {
+                let data = RevertField::try_from_slice(rest)?;
+                Self::RevertField(data)
+            }
+            SetKeySchema::SPL_DISCRIMINATOR_SLICE => {
+                let data = SetKeySchema::try_from_slice(rest)?;
+                Self::SetKeySchema(data)
+            }
+            BeginFieldWrite::SPL_DISCRIMINATOR_SLICE => {
+                let data = BeginFieldWrite::try_from_slice(rest)?;
+                Self::BeginFieldWrite(data)
+            }
+            WriteFieldChunk::SPL_DISCRIMINATOR_SLICE => {
+                let data = WriteFieldChunk::try_from_slice(rest)?;
+                Self::WriteFieldChunk(data)
+            }
+            CommitFieldWrite::SPL_DISCRIMINATOR_SLICE => {
+                let data = CommitFieldWrite::try_from_slice(rest)?;
+                Self::CommitFieldWrite(data)
+            }
+            InitializeProxy::SPL_DISCRIMINATOR_SLICE => {
+                let data = InitializeProxy::try_from_slice(rest)?;
+                Self::InitializeProxy(data)
+            }
+            UpdateFieldWithProxy::SPL_DISCRIMINATOR_SLICE => {
+                let data = UpdateFieldWithProxy::try_from_slice(rest)?;
+                Self::UpdateFieldWithProxy(data)
+            }
+            InitializeForSubject::SPL_DISCRIMINATOR_SLICE => {
+                let data = InitializeForSubject::try_from_slice(rest)?;
+                Self::InitializeForSubject(data)
+            }
+            InitializeStrict::SPL_DISCRIMINATOR_SLICE => {
+                let data = InitializeStrict::try_from_slice(rest)?;
+                Self::InitializeStrict(data)
+            }
+            SealMetadata::SPL_DISCRIMINATOR_SLICE => {
+                let data = SealMetadata::try_from_slice(rest)?;
+                Self::SealMetadata(data)
+            }
+            AbortFieldWrite::SPL_DISCRIMINATOR_SLICE => {
+                let data = AbortFieldWrite::try_from_slice(rest)?;
+                Self::AbortFieldWrite(data)
+            }
+            ReclaimProxyAuthority::SPL_DISCRIMINATOR_SLICE => {
+                let data = ReclaimProxyAuthority::try_from_slice(rest)?;
+                Self::ReclaimProxyAuthority(data)
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    /// Packs an [AdvancedTokenMetadataInstruction](enum.AdvancedTokenMetadataInstruction.html)
+    /// into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        match self {
+            Self::ConfigureFieldHistory(data) => {
+                buf.extend_from_slice(ConfigureFieldHistory::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::RevertField(data) => {
+                buf.extend_from_slice(RevertField::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::SetKeySchema(data) => {
+                buf.extend_from_slice(SetKeySchema::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::BeginFieldWrite(data) => {
+                buf.extend_from_slice(BeginFieldWrite::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::WriteFieldChunk(data) => {
+                buf.extend_from_slice(WriteFieldChunk::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::CommitFieldWrite(data) => {
+                buf.extend_from_slice(CommitFieldWrite::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::InitializeProxy(data) => {
+                buf.extend_from_slice(InitializeProxy::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::UpdateFieldWithProxy(data) => {
+                buf.extend_from_slice(UpdateFieldWithProxy::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::InitializeForSubject(data) => {
+                buf.extend_from_slice(InitializeForSubject::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::InitializeStrict(data) => {
+                buf.extend_from_slice(InitializeStrict::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::SealMetadata(data) => {
+                buf.extend_from_slice(SealMetadata::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::AbortFieldWrite(data) => {
+                buf.extend_from_slice(AbortFieldWrite::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+            Self::ReclaimProxyAuthority(data) => {
+                buf.extend_from_slice(ReclaimProxyAuthority::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
+        };
+        buf
+    }
+}
+
+/// Creates a `ConfigureFieldHistory` instruction
+pub fn configure_field_history(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+    field: Field,
+    max_versions: u8,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::ConfigureFieldHistory(ConfigureFieldHistory {
+        field,
+        max_versions,
+    });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*update_authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `RevertField` instruction
+pub fn revert_field(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    authority: &Pubkey,
+    field: Field,
+    version: u8,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::RevertField(RevertField { field, version });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `SetKeySchema` instruction
+pub fn set_key_schema(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+    entries: Vec<KeySchemaEntry>,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::SetKeySchema(SetKeySchema { entries });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*update_authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `BeginFieldWrite` instruction
+pub fn begin_field_write(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    authority: &Pubkey,
+    field: Field,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::BeginFieldWrite(BeginFieldWrite { field });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `WriteFieldChunk` instruction
+pub fn write_field_chunk(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    authority: &Pubkey,
+    offset: u32,
+    bytes: Vec<u8>,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::WriteFieldChunk(WriteFieldChunk { offset, bytes });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `CommitFieldWrite` instruction
+pub fn commit_field_write(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::CommitFieldWrite(CommitFieldWrite);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates an `AbortFieldWrite` instruction
+pub fn abort_field_write(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::AbortFieldWrite(AbortFieldWrite);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates an `InitializeProxy` instruction
+pub fn initialize_proxy(
+    program_id: &Pubkey,
+    proxy: &Pubkey,
+    mint: &Pubkey,
+    update_authority: &Pubkey,
+    payer: &Pubkey,
+    authorities: Vec<FieldAuthority>,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::InitializeProxy(InitializeProxy { authorities });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*proxy, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*update_authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates an `UpdateFieldWithProxy` instruction
+pub fn update_field_with_proxy(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    proxy: &Pubkey,
+    authority: &Pubkey,
+    field: Field,
+    value: String,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::UpdateFieldWithProxy(UpdateFieldWithProxy {
+        field,
+        value,
+    });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            // Records the field history
+            AccountMeta::new(*proxy, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `ReclaimProxyAuthority` instruction
+pub fn reclaim_proxy_authority(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    proxy: &Pubkey,
+    update_authority: &Pubkey,
+    destination: &Pubkey,
+    new_update_authority: Option<Pubkey>,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::ReclaimProxyAuthority(ReclaimProxyAuthority {
+        new_update_authority,
+    });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*proxy, false),
+            AccountMeta::new_readonly(*update_authority, true),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates an `InitializeForSubject` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_for_subject(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+    subject: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::InitializeForSubject(InitializeForSubject {
+        name,
+        symbol,
+        uri,
+    });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*update_authority, false),
+            AccountMeta::new_readonly(*subject, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates an `InitializeStrict` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_strict(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let data =
+        AdvancedTokenMetadataInstruction::InitializeStrict(InitializeStrict { name, symbol, uri });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*update_authority, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+        ],
+        data: data.pack(),
+    }
+}
+
+/// Creates a `SealMetadata` instruction
+pub fn seal_metadata(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+) -> Instruction {
+    let data = AdvancedTokenMetadataInstruction::SealMetadata(SealMetadata {});
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*update_authority, true),
+        ],
+        data: data.pack(),
+    }
+}
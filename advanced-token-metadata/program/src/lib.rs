@@ -4,9 +4,18 @@
 #![deny(missing_docs)]
 #![cfg_attr(not(test), forbid(unsafe_code))]
 
+pub mod error;
 pub mod field_authority;
 pub mod field_authority_v2;
+pub mod field_write;
+pub mod history;
+pub mod instruction;
 pub mod processor;
+pub mod proxy;
+pub mod schema;
+pub mod seal;
+pub mod state;
+pub mod subject;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
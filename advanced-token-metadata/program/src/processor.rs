@@ -2,37 +2,64 @@
 
 use {
     crate::{
+        error::AdvancedTokenMetadataError,
         field_authority::{
             process_add_field_authority, process_remove_field_authority,
             process_update_field_with_field_authority,
         },
         field_authority_v2::{
             process_add_field_authority_v2, process_initialize_field_authorities,
-            process_remove_field_authority_v2, process_update_field_with_field_authority_v2,
+            process_remove_field_authority_v2, process_remove_key_with_field_authority_v2,
+            process_update_field_with_field_authority_v2,
         },
+        field_write::{
+            process_abort_field_write, process_begin_field_write, process_commit_field_write,
+            process_write_field_chunk,
+        },
+        history::{process_configure_field_history, process_revert_field, record_field_history},
+        instruction::{AdvancedTokenMetadataInstruction, InitializeStrict},
+        proxy::{
+            process_initialize_proxy, process_reclaim_proxy_authority,
+            process_update_field_with_proxy,
+        },
+        schema::{check_key_schema, normalize_writable_field, process_set_key_schema},
+        seal::{check_not_sealed, process_seal_metadata},
+        subject::process_initialize_for_subject,
+    },
+    field_authority_interface::{
+        instructions::FieldAuthorityInstruction, normalize_key, RESERVED_KEY_PREFIX,
     },
-    field_authority_interface::instructions::FieldAuthorityInstruction,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
         borsh1::get_instance_packed_len,
         entrypoint::ProgramResult,
         msg,
-        program::set_return_data,
+        program::{invoke, invoke_signed, set_return_data},
         program_error::ProgramError,
         program_option::COption,
         pubkey::Pubkey,
+        system_instruction,
+        sysvar::{rent::Rent, Sysvar},
     },
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
     spl_pod::optional_keys::OptionalNonZeroPubkey,
-    spl_token_2022::{extension::StateWithExtensions, state::Mint},
+    spl_token_2022::{
+        check_spl_token_program_account,
+        extension::{
+            metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint,
+    },
     spl_token_metadata_interface::{
         error::TokenMetadataError,
         instruction::{
             Emit, Initialize, RemoveKey, TokenMetadataInstruction, UpdateAuthority, UpdateField,
         },
-        state::TokenMetadata,
+        state::{Field, TokenMetadata},
     },
-    spl_type_length_value::state::{
-        realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed, TlvStateMut,
+    spl_type_length_value::{
+        state::{realloc_and_pack_first_variable_len, TlvState, TlvStateBorrowed, TlvStateMut},
+        variable_len_pack::VariableLenPack,
     },
 };
 
@@ -52,12 +79,179 @@ pub fn check_update_authority(
     Ok(())
 }
 
+/// Get the current value of a field, if it's set
+pub fn get_field_value(token_metadata: &TokenMetadata, field: &Field) -> Option<String> {
+    match field {
+        Field::Name => Some(token_metadata.name.clone()),
+        Field::Symbol => Some(token_metadata.symbol.clone()),
+        Field::Uri => Some(token_metadata.uri.clone()),
+        Field::Key(key) => {
+            // Keys written before normalization are matched by their normalized form
+            let key = normalize_key(key);
+            token_metadata
+                .additional_metadata
+                .iter()
+                .find(|(k, _)| normalize_key(k) == key)
+                .map(|(_, v)| v.clone())
+        }
+    }
+}
+
+/// Set the value of a field. A key written before normalization is replaced in place under its
+/// normalized form, rather than kept next to the normalized one.
+pub fn set_field_value(token_metadata: &mut TokenMetadata, field: Field, value: String) {
+    let Field::Key(key) = field else {
+        token_metadata.update(field, value);
+        return;
+    };
+    let key = normalize_key(&key);
+    let mut replaced = false;
+    token_metadata.additional_metadata.retain_mut(|(k, v)| {
+        if normalize_key(k) != key {
+            return true;
+        }
+        if replaced {
+            return false;
+        }
+        *k = key.clone();
+        *v = value.clone();
+        replaced = true;
+        true
+    });
+    if !replaced {
+        token_metadata.additional_metadata.push((key, value));
+    }
+}
+
+/// Get the first TLV entry of the given type from the account, if there is one
+pub fn get_optional_variable_len_value<V: SplDiscriminate + VariableLenPack>(
+    account_info: &AccountInfo,
+) -> Result<Option<V>, ProgramError> {
+    let buffer = account_info.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    if !state.get_discriminators()?.contains(&V::SPL_DISCRIMINATOR) {
+        return Ok(None);
+    }
+    state.get_first_variable_len_value::<V>().map(Some)
+}
+
+/// Get the number of bytes taken up by the TLV entries in the buffer
+fn get_tlv_data_len(data: &[u8]) -> usize {
+    let base_len = TlvStateBorrowed::get_base_len();
+    let mut end = 0;
+    while end + base_len <= data.len() {
+        let discriminator = &data[end..end + ArrayDiscriminator::LENGTH];
+        if discriminator == ArrayDiscriminator::UNINITIALIZED.as_slice() {
+            break;
+        }
+        let length_bytes = &data[end + ArrayDiscriminator::LENGTH..end + base_len];
+        let length = u32::from_le_bytes(length_bytes.try_into().unwrap()) as usize;
+        end += base_len + length;
+    }
+    end
+}
+
+/// Allocate a new TLV entry after the existing ones, reallocating the account if there isn't
+/// enough unused space
+pub fn alloc_and_pack_variable_len_entry<V: SplDiscriminate + VariableLenPack>(
+    account_info: &AccountInfo,
+    value: &V,
+) -> ProgramResult {
+    let required_len = {
+        let buffer = account_info.try_borrow_data()?;
+        get_tlv_data_len(&buffer) + TlvStateBorrowed::get_base_len() + value.get_packed_len()?
+    };
+    if account_info.data_len() < required_len {
+        account_info.realloc(required_len, true)?;
+    }
+
+    let mut buffer = account_info.try_borrow_mut_data()?;
+    let mut state = TlvStateMut::unpack(&mut buffer)?;
+    state.alloc_and_pack_variable_len_entry(value, false)?;
+
+    Ok(())
+}
+
+/// Create a PDA owned by the program. Unlike `create_account` this still works when someone has
+/// already sent lamports to the address, only the shortfall is paid.
+pub fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    pda_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let required_lamports = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(pda_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, pda_info.key, required_lamports),
+            &[
+                payer_info.clone(),
+                pda_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+
+    let account_infos = [pda_info.clone(), system_program_info.clone()];
+    invoke_signed(
+        &system_instruction::allocate(pda_info.key, space as u64),
+        &account_infos,
+        signer_seeds,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(pda_info.key, program_id),
+        &account_infos,
+        signer_seeds,
+    )
+}
+
+/// Checks the mint is owned by a token program and, for Token-2022 mints, that its metadata
+/// pointer points to the metadata account
+fn check_strict_mint(metadata_info: &AccountInfo, mint_info: &AccountInfo) -> ProgramResult {
+    check_spl_token_program_account(mint_info.owner).map_err(|_| ProgramError::IllegalOwner)?;
+    if *mint_info.owner == spl_token_2022::id() {
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let metadata_address = mint
+            .get_extension::<MetadataPointer>()
+            .ok()
+            .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address));
+        if metadata_address != Some(*metadata_info.key) {
+            return Err(AdvancedTokenMetadataError::MetadataPointerMismatch.into());
+        }
+    }
+    Ok(())
+}
+
 /// Processes a [Initialize](enum.TokenMetadataInstruction.html) instruction.
 pub fn process_initialize(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: Initialize,
 ) -> ProgramResult {
+    initialize_metadata(accounts, data, false)
+}
+
+/// Processes an InitializeStrict instruction
+pub fn process_initialize_strict(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: InitializeStrict,
+) -> ProgramResult {
+    let data = Initialize {
+        name: data.name,
+        symbol: data.symbol,
+        uri: data.uri,
+    };
+    initialize_metadata(accounts, data, true)
+}
+
+/// Initializes the metadata, optionally with the strict checks of the mint
+fn initialize_metadata(accounts: &[AccountInfo], data: Initialize, strict: bool) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let metadata_info = next_account_info(account_info_iter)?;
@@ -65,6 +259,10 @@ pub fn process_initialize(
     let mint_info = next_account_info(account_info_iter)?;
     let mint_authority_info = next_account_info(account_info_iter)?;
 
+    if strict {
+        check_strict_mint(metadata_info, mint_info)?;
+    }
+
     // scope the mint authority check, in case the mint is in the same account!
     {
         // IMPORTANT: this example metadata program is designed to work with any
@@ -106,8 +304,10 @@ pub fn process_initialize(
 pub fn process_update_field(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    data: UpdateField,
+    mut data: UpdateField,
 ) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let update_authority_info = next_account_info(account_info_iter)?;
@@ -121,9 +321,10 @@ pub fn process_update_field(
     };
 
     check_update_authority(update_authority_info, &token_metadata.update_authority)?;
+    check_not_sealed(metadata_info)?;
 
     // Update the field
-    token_metadata.update(data.field, data.value);
+    update_field_tracked(metadata_info, &mut token_metadata, data.field, data.value)?;
 
     // Update / realloc the account
     realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
@@ -131,12 +332,31 @@ pub fn process_update_field(
     Ok(())
 }
 
+/// Updates a field after checking it against the key schema, keeping the previous value if the
+/// field is tracked. Every field update goes through here.
+pub fn update_field_tracked(
+    metadata_info: &AccountInfo,
+    token_metadata: &mut TokenMetadata,
+    field: Field,
+    value: String,
+) -> ProgramResult {
+    check_key_schema(metadata_info, &field, &value)?;
+    record_field_history(metadata_info, token_metadata, &field, &value)?;
+    set_field_value(token_metadata, field, value);
+    Ok(())
+}
+
 /// Processes a [RemoveKey](enum.TokenMetadataInstruction.html) instruction.
 pub fn process_remove_key(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: RemoveKey,
 ) -> ProgramResult {
+    let key = normalize_key(&data.key);
+    if key.starts_with(RESERVED_KEY_PREFIX) {
+        return Err(AdvancedTokenMetadataError::ReservedKey.into());
+    }
+
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
     let update_authority_info = next_account_info(account_info_iter)?;
@@ -150,7 +370,14 @@ pub fn process_remove_key(
     };
 
     check_update_authority(update_authority_info, &token_metadata.update_authority)?;
-    if !token_metadata.remove_key(&data.key) && !data.idempotent {
+    check_not_sealed(metadata_info)?;
+
+    // Keys written before normalization are matched by their normalized form
+    let previous_len = token_metadata.additional_metadata.len();
+    token_metadata
+        .additional_metadata
+        .retain(|(k, _)| normalize_key(k) != key);
+    if token_metadata.additional_metadata.len() == previous_len && !data.idempotent {
         return Err(TokenMetadataError::KeyNotFound.into());
     }
     realloc_and_pack_first_variable_len(metadata_info, &token_metadata)?;
@@ -239,6 +466,69 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> P
                 msg!("Instruction: RemoveFieldAuthorityV2");
                 return process_remove_field_authority_v2(program_id, accounts, data);
             }
+            FieldAuthorityInstruction::RemoveKeyWithFieldAuthorityV2(data) => {
+                msg!("Instruction: RemoveKeyWithFieldAuthorityV2");
+                return process_remove_key_with_field_authority_v2(program_id, accounts, data);
+            }
+        }
+    }
+
+    // Then match against AdvancedTokenMetadataInstruction
+
+    if let Ok(advanced_ix) = AdvancedTokenMetadataInstruction::unpack(input) {
+        match advanced_ix {
+            AdvancedTokenMetadataInstruction::ConfigureFieldHistory(data) => {
+                msg!("Instruction: ConfigureFieldHistory");
+                return process_configure_field_history(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::RevertField(data) => {
+                msg!("Instruction: RevertField");
+                return process_revert_field(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::SetKeySchema(data) => {
+                msg!("Instruction: SetKeySchema");
+                return process_set_key_schema(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::BeginFieldWrite(data) => {
+                msg!("Instruction: BeginFieldWrite");
+                return process_begin_field_write(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::WriteFieldChunk(data) => {
+                msg!("Instruction: WriteFieldChunk");
+                return process_write_field_chunk(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::CommitFieldWrite(data) => {
+                msg!("Instruction: CommitFieldWrite");
+                return process_commit_field_write(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::InitializeProxy(data) => {
+                msg!("Instruction: InitializeProxy");
+                return process_initialize_proxy(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::UpdateFieldWithProxy(data) => {
+                msg!("Instruction: UpdateFieldWithProxy");
+                return process_update_field_with_proxy(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::ReclaimProxyAuthority(data) => {
+                msg!("Instruction: ReclaimProxyAuthority");
+                return process_reclaim_proxy_authority(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::InitializeForSubject(data) => {
+                msg!("Instruction: InitializeForSubject");
+                return process_initialize_for_subject(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::InitializeStrict(data) => {
+                msg!("Instruction: InitializeStrict");
+                return process_initialize_strict(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::SealMetadata(data) => {
+                msg!("Instruction: SealMetadata");
+                return process_seal_metadata(program_id, accounts, data);
+            }
+            AdvancedTokenMetadataInstruction::AbortFieldWrite(data) => {
+                msg!("Instruction: AbortFieldWrite");
+                return process_abort_field_write(program_id, accounts, data);
+            }
         }
     }
 
@@ -0,0 +1,257 @@
+//! Field authorities for metadata embedded in a Token-2022 mint, through a proxy PDA that
+//! forwards updates to Token-2022
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        instruction::{InitializeProxy, ReclaimProxyAuthority, UpdateFieldWithProxy},
+        processor::{
+            check_update_authority, create_pda_account, get_optional_variable_len_value,
+            update_field_tracked,
+        },
+        schema::normalize_writable_field,
+        seal::check_not_sealed,
+        state::{find_proxy_authority_address, ProxyAuthority, PROXY_AUTHORITY_SEED},
+    },
+    field_authority_interface::{
+        errors::FieldAuthorityError,
+        state::{FieldAuthorities, FieldAuthority},
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_2022::{
+        extension::{BaseStateWithExtensions, StateWithExtensions},
+        state::Mint,
+    },
+    spl_token_metadata_interface::{
+        instruction::{update_authority, update_field},
+        state::TokenMetadata,
+    },
+    spl_type_length_value::{
+        state::{TlvState, TlvStateBorrowed, TlvStateMut},
+        variable_len_pack::VariableLenPack,
+    },
+};
+
+/// Checks the program is Token-2022, the only one storing metadata in the mint
+fn check_token_2022_program(token_program_info: &AccountInfo) -> Result<(), ProgramError> {
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Returns the metadata embedded in the mint
+fn get_embedded_metadata(mint_info: &AccountInfo) -> Result<TokenMetadata, ProgramError> {
+    if *mint_info.owner != spl_token_2022::id() {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    mint.get_variable_len_extension::<TokenMetadata>()
+}
+
+/// Checks the proxy account is the initialized proxy PDA of the mint. Returns its proxy
+/// authority entry and bump.
+pub fn check_proxy_authority(
+    program_id: &Pubkey,
+    proxy_info: &AccountInfo,
+    mint_info: &AccountInfo,
+) -> Result<(ProxyAuthority, u8), ProgramError> {
+    let (proxy, bump) = find_proxy_authority_address(program_id, mint_info.key);
+    if *proxy_info.key != proxy || proxy_info.owner != program_id {
+        return Err(AdvancedTokenMetadataError::IncorrectProxyAuthority.into());
+    }
+    let proxy_authority = get_optional_variable_len_value::<ProxyAuthority>(proxy_info)?
+        .ok_or(AdvancedTokenMetadataError::IncorrectProxyAuthority)?;
+    Ok((proxy_authority, bump))
+}
+
+/// Processes an InitializeProxy instruction
+pub fn process_initialize_proxy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: InitializeProxy,
+) -> ProgramResult {
+    for field_authority in data.authorities.iter_mut() {
+        field_authority.field = normalize_writable_field(field_authority.field.clone())?;
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let proxy_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    check_token_2022_program(token_program_info)?;
+
+    let (proxy, bump) = find_proxy_authority_address(program_id, mint_info.key);
+    if *proxy_info.key != proxy {
+        return Err(AdvancedTokenMetadataError::IncorrectProxyAuthority.into());
+    }
+
+    let token_metadata = get_embedded_metadata(mint_info)?;
+    check_update_authority(update_authority_info, &token_metadata.update_authority)?;
+
+    let proxy_authority = ProxyAuthority {
+        mint: *mint_info.key,
+        update_authority: Some(*update_authority_info.key),
+    };
+    let field_authorities = FieldAuthorities {
+        authorities: data.authorities,
+    };
+
+    // Create the proxy account with room for both entries
+    let space = 2 * TlvStateBorrowed::get_base_len()
+        + proxy_authority.get_packed_len()?
+        + field_authorities.get_packed_len()?;
+    let signer_seeds: &[&[&[u8]]] = &[&[PROXY_AUTHORITY_SEED, mint_info.key.as_ref(), &[bump]]];
+    create_pda_account(
+        program_id,
+        payer_info,
+        proxy_info,
+        system_program_info,
+        space,
+        signer_seeds,
+    )?;
+
+    {
+        let mut buffer = proxy_info.try_borrow_mut_data()?;
+        let mut state = TlvStateMut::unpack(&mut buffer)?;
+        state.alloc_and_pack_variable_len_entry(&proxy_authority, false)?;
+        state.alloc_and_pack_variable_len_entry(&field_authorities, false)?;
+    }
+
+    // Hand the embedded metadata over to the proxy
+    let update_authority_ix = update_authority(
+        token_program_info.key,
+        mint_info.key,
+        update_authority_info.key,
+        OptionalNonZeroPubkey::try_from(Some(proxy))?,
+    );
+    invoke(
+        &update_authority_ix,
+        &[mint_info.clone(), update_authority_info.clone()],
+    )?;
+
+    Ok(())
+}
+
+/// Processes an UpdateFieldWithProxy instruction
+pub fn process_update_field_with_proxy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: UpdateFieldWithProxy,
+) -> ProgramResult {
+    data.field = normalize_writable_field(data.field)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let mint_info = next_account_info(account_info_iter)?;
+    let proxy_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    check_token_2022_program(token_program_info)?;
+
+    let (proxy_authority, bump) = check_proxy_authority(program_id, proxy_info, mint_info)?;
+
+    // The previous update authority keeps full control until the proxy is sealed, field
+    // authorities only their field
+    let update_authority = OptionalNonZeroPubkey::try_from(proxy_authority.update_authority)?;
+    if check_update_authority(authority_info, &update_authority).is_err()
+        || check_not_sealed(proxy_info).is_err()
+    {
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let field_authorities = get_optional_variable_len_value::<FieldAuthorities>(proxy_info)?
+            .ok_or(FieldAuthorityError::IncorrectFieldAuthority)?;
+        let field_authority = FieldAuthority {
+            field: data.field.clone(),
+            authority: *authority_info.key,
+        };
+        if !field_authorities.contains_field_authority(field_authority) {
+            return Err(FieldAuthorityError::IncorrectFieldAuthority.into());
+        }
+    }
+
+    // The proxy account holds the key schema and history of the embedded metadata
+    let mut token_metadata = get_embedded_metadata(mint_info)?;
+    update_field_tracked(
+        proxy_info,
+        &mut token_metadata,
+        data.field.clone(),
+        data.value.clone(),
+    )?;
+
+    // Token-2022 doesn't top up rent, so the mint must be funded for the new value
+    let update_field_ix = update_field(
+        token_program_info.key,
+        mint_info.key,
+        proxy_info.key,
+        data.field,
+        data.value,
+    );
+    let signer_seeds: &[&[&[u8]]] = &[&[PROXY_AUTHORITY_SEED, mint_info.key.as_ref(), &[bump]]];
+    invoke_signed(
+        &update_field_ix,
+        &[mint_info.clone(), proxy_info.clone()],
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+/// Processes a ReclaimProxyAuthority instruction
+pub fn process_reclaim_proxy_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: ReclaimProxyAuthority,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let mint_info = next_account_info(account_info_iter)?;
+    let proxy_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    check_token_2022_program(token_program_info)?;
+
+    let (proxy_authority, bump) = check_proxy_authority(program_id, proxy_info, mint_info)?;
+    let expected_update_authority =
+        OptionalNonZeroPubkey::try_from(proxy_authority.update_authority)?;
+    check_update_authority(update_authority_info, &expected_update_authority)?;
+    // Reclaiming would otherwise lift the seal
+    check_not_sealed(proxy_info)?;
+
+    let update_authority_ix = update_authority(
+        token_program_info.key,
+        mint_info.key,
+        proxy_info.key,
+        OptionalNonZeroPubkey::try_from(data.new_update_authority)?,
+    );
+    let signer_seeds: &[&[&[u8]]] = &[&[PROXY_AUTHORITY_SEED, mint_info.key.as_ref(), &[bump]]];
+    invoke_signed(
+        &update_authority_ix,
+        &[mint_info.clone(), proxy_info.clone()],
+        signer_seeds,
+    )?;
+
+    // Close the proxy account, so the mint can be proxied again later
+    let lamports = proxy_info.lamports();
+    **proxy_info.try_borrow_mut_lamports()? -= lamports;
+    **destination_info.try_borrow_mut_lamports()? += lamports;
+    proxy_info.assign(&system_program::id());
+    proxy_info.realloc(0, false)?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Key schema and reserved namespaces restricting which additional metadata keys may exist
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        field_authority::check_metadata_update_authority,
+        instruction::SetKeySchema,
+        processor::{alloc_and_pack_variable_len_entry, get_optional_variable_len_value},
+        state::KeySchema,
+    },
+    field_authority_interface::{
+        is_reserved_field, normalize_field, normalize_key, RESERVED_KEY_PREFIX,
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    spl_token_metadata_interface::state::Field,
+    spl_type_length_value::state::realloc_and_pack_first_variable_len,
+};
+
+/// Normalizes a field that's about to be written, rejecting keys in the reserved namespace
+pub fn normalize_writable_field(field: Field) -> Result<Field, ProgramError> {
+    if is_reserved_field(&field) {
+        return Err(AdvancedTokenMetadataError::ReservedKey.into());
+    }
+    Ok(normalize_field(field))
+}
+
+/// Checks an update against the key schema, if one is set. Name, symbol and URI are always
+/// allowed.
+pub fn check_key_schema(metadata_info: &AccountInfo, field: &Field, value: &str) -> ProgramResult {
+    let Field::Key(key) = field else {
+        return Ok(());
+    };
+    let Some(key_schema) = get_optional_variable_len_value::<KeySchema>(metadata_info)? else {
+        return Ok(());
+    };
+
+    let value_type = key_schema
+        .get_value_type(key)
+        .ok_or(AdvancedTokenMetadataError::UnknownKey)?;
+    if !value_type.is_valid(value) {
+        return Err(AdvancedTokenMetadataError::InvalidValueType.into());
+    }
+
+    Ok(())
+}
+
+/// Processes a SetKeySchema instruction
+pub fn process_set_key_schema(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut data: SetKeySchema,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+
+    check_metadata_update_authority(metadata_info, update_authority_info)?;
+
+    for entry in data.entries.iter_mut() {
+        entry.key = normalize_key(&entry.key);
+        if entry.key.starts_with(RESERVED_KEY_PREFIX) {
+            return Err(AdvancedTokenMetadataError::ReservedKey.into());
+        }
+    }
+
+    // Existing keys aren't checked, so a schema can be tightened without touching stored values
+    let key_schema = KeySchema {
+        entries: data.entries,
+    };
+    if get_optional_variable_len_value::<KeySchema>(metadata_info)?.is_some() {
+        realloc_and_pack_first_variable_len(metadata_info, &key_schema)?;
+    } else {
+        alloc_and_pack_variable_len_entry(metadata_info, &key_schema)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! Sealing metadata, so only its existing field authorities can update it
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        field_authority::check_metadata_update_authority,
+        instruction::SealMetadata,
+        processor::{alloc_and_pack_variable_len_entry, get_optional_variable_len_value},
+        state::SealedMetadata,
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+/// Checks the metadata isn't sealed, which the update authority needs for anything but handing
+/// over its role
+pub fn check_not_sealed(metadata_info: &AccountInfo) -> Result<(), ProgramError> {
+    if get_optional_variable_len_value::<SealedMetadata>(metadata_info)?.is_some() {
+        return Err(AdvancedTokenMetadataError::MetadataSealed.into());
+    }
+    Ok(())
+}
+
+/// Processes a SealMetadata instruction
+pub fn process_seal_metadata(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: SealMetadata,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+
+    // Also fails if the metadata is already sealed
+    check_metadata_update_authority(metadata_info, update_authority_info)?;
+
+    // Allocate a TLV entry, reallocating since the account wasn't created with room for it
+    alloc_and_pack_variable_len_entry(metadata_info, &SealedMetadata::default())?;
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+//! TLV entries stored alongside `TokenMetadata` in the metadata account, or in a proxy account
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        borsh1::{get_instance_packed_len, try_from_slice_unchecked},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    spl_discriminator::SplDiscriminate,
+    spl_token_metadata_interface::state::Field,
+    spl_type_length_value::variable_len_pack::VariableLenPack,
+    std::str::FromStr,
+};
+
+/// Implements `VariableLenPack` for a TLV entry through its borsh encoding
+macro_rules! impl_borsh_variable_len_pack {
+    ($entry:ty) => {
+        impl VariableLenPack for $entry {
+            fn pack_into_slice(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+                borsh::to_writer(&mut dst[..], self).map_err(Into::into)
+            }
+
+            fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+                try_from_slice_unchecked(src).map_err(Into::into)
+            }
+
+            fn get_packed_len(&self) -> Result<usize, ProgramError> {
+                get_instance_packed_len(self).map_err(Into::into)
+            }
+        }
+    };
+}
+
+/// Bounded list of previous values for a single field, most recent first
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct FieldHistory {
+    /// The field being tracked
+    pub field: Field,
+    /// Maximum number of previous values kept
+    pub max_versions: u8,
+    /// Previous values, most recent first
+    pub values: Vec<String>,
+}
+
+/// Opt-in value history for selected fields
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:field_histories")]
+pub struct FieldHistories {
+    /// Histories, one per tracked field
+    pub histories: Vec<FieldHistory>,
+}
+impl_borsh_variable_len_pack!(FieldHistories);
+impl FieldHistories {
+    /// Returns the history of a field, if it is tracked
+    pub fn get_mut(&mut self, field: &Field) -> Option<&mut FieldHistory> {
+        self.histories.iter_mut().find(|h| h.field == *field)
+    }
+
+    /// Sets the maximum number of versions kept for a field. A maximum of zero stops tracking
+    /// the field and drops its history.
+    pub fn configure(&mut self, field: Field, max_versions: u8) {
+        if max_versions == 0 {
+            self.histories.retain(|h| h.field != field);
+            return;
+        }
+        match self.get_mut(&field) {
+            Some(history) => {
+                history.max_versions = max_versions;
+                history.values.truncate(max_versions as usize);
+            }
+            None => self.histories.push(FieldHistory {
+                field,
+                max_versions,
+                values: vec![],
+            }),
+        }
+    }
+
+    /// Records a previous value for a field. Returns true if the field is tracked.
+    pub fn record(&mut self, field: &Field, value: String) -> bool {
+        match self.get_mut(field) {
+            Some(history) => {
+                history.values.insert(0, value);
+                history.values.truncate(history.max_versions as usize);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Type of value allowed for an additional metadata key
+#[derive(Clone, Copy, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub enum KeyValueType {
+    /// Any string
+    String,
+    /// Signed 64-bit integer in base 10
+    Integer,
+    /// `true` or `false`
+    Bool,
+    /// Base58 encoded public key
+    Pubkey,
+    /// URL with a scheme, e.g. `https://`, and no whitespace
+    Url,
+}
+impl KeyValueType {
+    /// Checks whether a value is valid for this type. The empty value is valid for every type,
+    /// so a key can always be cleared.
+    pub fn is_valid(&self, value: &str) -> bool {
+        if value.is_empty() {
+            return true;
+        }
+        match self {
+            KeyValueType::String => true,
+            KeyValueType::Integer => value.parse::<i64>().is_ok(),
+            KeyValueType::Bool => value == "true" || value == "false",
+            KeyValueType::Pubkey => Pubkey::from_str(value).is_ok(),
+            KeyValueType::Url => match value.split_once("://") {
+                Some((scheme, rest)) => {
+                    !scheme.is_empty()
+                        && scheme
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                        && !rest.is_empty()
+                        && !value.chars().any(char::is_whitespace)
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// An additional metadata key allowed by the schema
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct KeySchemaEntry {
+    /// The allowed key
+    pub key: String,
+    /// The type of value allowed for the key
+    pub value_type: KeyValueType,
+}
+
+/// Declares which additional metadata keys may exist and the type of their values. Once set,
+/// updating any key not listed is rejected.
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:key_schema")]
+pub struct KeySchema {
+    /// Allowed keys
+    pub entries: Vec<KeySchemaEntry>,
+}
+impl_borsh_variable_len_pack!(KeySchema);
+impl KeySchema {
+    /// Returns the allowed value type of a key, if the key is in the schema
+    pub fn get_value_type(&self, key: &str) -> Option<KeyValueType> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.value_type)
+    }
+}
+
+/// Seconds after which a write in progress can be taken over by another authority
+pub const FIELD_WRITE_EXPIRY_SECONDS: i64 = 3600;
+
+/// Buffer staging a field value too large for a single transaction. Chunks are written into
+/// the buffer, then the whole value is swapped into the field at once. There is a single buffer
+/// per metadata account, owned by the authority that began the write until it's committed,
+/// aborted, or expired.
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:field_write_buffer")]
+pub struct FieldWriteBuffer {
+    /// Field being written
+    pub field: Field,
+    /// Authority that started the write, or `None` if no write is in progress
+    pub authority: Option<Pubkey>,
+    /// Unix timestamp the write began at
+    pub started_at: i64,
+    /// Staged bytes of the new value
+    pub data: Vec<u8>,
+}
+impl_borsh_variable_len_pack!(FieldWriteBuffer);
+impl FieldWriteBuffer {
+    /// Checks if another authority owns a write that hasn't expired yet
+    pub fn is_owned_by_other(&self, authority: &Pubkey, now: i64) -> bool {
+        match self.authority {
+            Some(owner) => {
+                owner != *authority
+                    && now < self.started_at.saturating_add(FIELD_WRITE_EXPIRY_SECONDS)
+            }
+            None => false,
+        }
+    }
+
+    /// Writes bytes at an offset, growing the staged data as needed. The offset can't be past
+    /// the end of the staged data, so the value never has gaps.
+    pub fn write(&mut self, offset: usize, bytes: &[u8]) -> bool {
+        if offset > self.data.len() {
+            return false;
+        }
+        let end = offset + bytes.len();
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+        self.data[offset..end].copy_from_slice(bytes);
+        true
+    }
+}
+
+/// Seed of the proxy PDA of a mint, derived from `["proxy-authority", mint]`
+pub const PROXY_AUTHORITY_SEED: &[u8] = b"proxy-authority";
+
+/// Finds the proxy PDA of a mint
+pub fn find_proxy_authority_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROXY_AUTHORITY_SEED, mint.as_ref()], program_id)
+}
+
+/// Stored in the proxy PDA, which is the update authority of the metadata embedded in a
+/// Token-2022 mint. The proxy account also stores the `FieldAuthorities` of the mint.
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:proxy_authority")]
+pub struct ProxyAuthority {
+    /// Mint storing the metadata
+    pub mint: Pubkey,
+    /// Update authority of the metadata before the proxy took over, which keeps full control
+    pub update_authority: Option<Pubkey>,
+}
+impl_borsh_variable_len_pack!(ProxyAuthority);
+
+/// Marks metadata as sealed. The update authority can no longer write the metadata or manage
+/// its field authorities, while the existing field authorities keep updating their fields.
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
+#[discriminator_hash_input("advanced_token_metadata:sealed_metadata")]
+pub struct SealedMetadata {}
+impl_borsh_variable_len_pack!(SealedMetadata);
+
+/// Seed of the metadata PDA of a subject, derived from `["subject-metadata", subject]`
+pub const SUBJECT_METADATA_SEED: &[u8] = b"subject-metadata";
+
+/// Finds the metadata PDA of a subject, which can be any account rather than a mint
+pub fn find_subject_metadata_address(program_id: &Pubkey, subject: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SUBJECT_METADATA_SEED, subject.as_ref()], program_id)
+}
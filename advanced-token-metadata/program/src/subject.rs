@@ -0,0 +1,74 @@
+//! Metadata for arbitrary accounts rather than mints, stored in a PDA derived from the subject
+
+use {
+    crate::{
+        error::AdvancedTokenMetadataError,
+        instruction::InitializeForSubject,
+        processor::create_pda_account,
+        state::{find_subject_metadata_address, SUBJECT_METADATA_SEED},
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        borsh1::get_instance_packed_len,
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::TokenMetadata,
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed, TlvStateMut},
+};
+
+/// Processes an InitializeForSubject instruction
+pub fn process_initialize_for_subject(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: InitializeForSubject,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let subject_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Signing proves control of the subject, PDAs sign through their program
+    if !subject_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (metadata, bump) = find_subject_metadata_address(program_id, subject_info.key);
+    if *metadata_info.key != metadata {
+        return Err(AdvancedTokenMetadataError::IncorrectSubjectMetadata.into());
+    }
+
+    // The subject takes the place of the mint, so the metadata is read like any other
+    let token_metadata = TokenMetadata {
+        name: data.name,
+        symbol: data.symbol,
+        uri: data.uri,
+        update_authority: OptionalNonZeroPubkey::try_from(Some(*update_authority_info.key))?,
+        mint: *subject_info.key,
+        ..Default::default()
+    };
+    let instance_size = get_instance_packed_len(&token_metadata)?;
+
+    // Create the metadata PDA with room for the metadata only, later entries realloc it
+    let space = TlvStateBorrowed::get_base_len() + instance_size;
+    let signer_seeds: &[&[&[u8]]] = &[&[SUBJECT_METADATA_SEED, subject_info.key.as_ref(), &[bump]]];
+    create_pda_account(
+        program_id,
+        payer_info,
+        metadata_info,
+        system_program_info,
+        space,
+        signer_seeds,
+    )?;
+
+    let mut buffer = metadata_info.try_borrow_mut_data()?;
+    let mut state = TlvStateMut::unpack(&mut buffer)?;
+    state.alloc::<TokenMetadata>(instance_size, false)?;
+    state.pack_first_variable_len_value(&token_metadata)?;
+
+    Ok(())
+}
@@ -0,0 +1,280 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    field_authority_interface::{
+        errors::FieldAuthorityError,
+        field_to_seed, find_field_pda,
+        instructions::{
+            add_field_authority, remove_field_authority, update_field_with_field_authority,
+        },
+        resolve_field_pda_seed, FieldSeedVersion, FIELD_AUTHORITY_PDA_SEED,
+    },
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::tokio,
+    solana_sdk::{
+        instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+    test_case::test_case,
+};
+
+#[test]
+fn resolve_field_pda_any_version() {
+    let program_id = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+    let field = Field::Key("nickname".to_string());
+    for version in FieldSeedVersion::ALL {
+        let (field_pda, bump) =
+            find_field_pda(&program_id, &metadata, field.clone(), version).unwrap();
+        let resolved = resolve_field_pda_seed(&program_id, &metadata, field.clone(), &field_pda);
+        assert_eq!(
+            resolved,
+            Some((field_to_seed(field.clone(), version).unwrap(), bump))
+        );
+    }
+    assert_eq!(
+        resolve_field_pda_seed(&program_id, &metadata, field, &Pubkey::new_unique()),
+        None
+    );
+}
+
+#[test_case("nickname", FieldSeedVersion::Raw ; "short key")]
+#[test_case("a_key_that_is_much_too_long_for_a_single_seed", FieldSeedVersion::Hashed ; "long key")]
+#[tokio::test]
+async fn success_add_update_remove(key: &str, expected_version: FieldSeedVersion) {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let field = Field::Key(key.to_string());
+    assert_eq!(FieldSeedVersion::for_field(&field), expected_version);
+
+    // short keys keep the original raw seed derivation
+    let (field_pda, _bump) = find_field_pda(
+        &program_id,
+        &metadata_pubkey,
+        field.clone(),
+        expected_version,
+    )
+    .unwrap();
+    if expected_version == FieldSeedVersion::Raw {
+        let (legacy_field_pda, _bump) = Pubkey::find_program_address(
+            &[
+                FIELD_AUTHORITY_PDA_SEED.as_bytes(),
+                format!("key:{}", key).as_bytes(),
+                metadata_pubkey.as_ref(),
+            ],
+            &program_id,
+        );
+        assert_eq!(field_pda, legacy_field_pda);
+    }
+
+    let field_authority = Keypair::new();
+    let add_ix = add_field_authority(
+        &program_id,
+        &context.payer.pubkey(),
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+        &field_authority.pubkey(),
+    );
+    process_instruction(&mut context, add_ix, &update_authority)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(field_pda)
+        .await
+        .unwrap()
+        .is_some());
+
+    let update_ix = update_field_with_field_authority(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field.clone(),
+        "value".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(
+        fetched_metadata.additional_metadata,
+        vec![(key.to_string(), "value".to_string())]
+    );
+
+    let remove_ix = remove_field_authority(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field,
+    );
+    process_instruction(&mut context, remove_ix, &update_authority)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(field_pda)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn fail_update_with_field_authority() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let token = setup_mint(
+        &spl_token_2022::id(),
+        &mint_authority.pubkey(),
+        2,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let field = Field::Key("nickname".to_string());
+    let field_authority = Keypair::new();
+    let add_ix = add_field_authority(
+        &program_id,
+        &context.payer.pubkey(),
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+        &field_authority.pubkey(),
+    );
+    process_instruction(&mut context, add_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // the field authority must sign, passing its key isn't enough
+    let mut update_ix = update_field_with_field_authority(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field.clone(),
+        "value".to_string(),
+    );
+    update_ix.accounts[1].is_signer = false;
+    let error = process_instruction(&mut context, update_ix, &payer)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // a correctly derived field PDA that isn't initialized is rejected
+    let remove_ix = remove_field_authority(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+    );
+    process_instruction(&mut context, remove_ix, &update_authority)
+        .await
+        .unwrap();
+    let update_ix = update_field_with_field_authority(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field,
+        "value".to_string(),
+    );
+    let error = process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldPda as u32)
+        )
+    );
+}
@@ -0,0 +1,284 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError,
+        instruction::{configure_field_history, revert_field},
+        state::{FieldHistories, FieldHistory},
+    },
+    field_authority_interface::errors::FieldAuthorityError,
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::{tokio, ProgramTestContext},
+    solana_sdk::{
+        instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::{
+        instruction::update_field,
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+async fn fetch_state(
+    context: &mut ProgramTestContext,
+    metadata: &Pubkey,
+) -> (TokenMetadata, FieldHistories) {
+    let account = context
+        .banks_client
+        .get_account(*metadata)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = TlvStateBorrowed::unpack(&account.data).unwrap();
+    (
+        state
+            .get_first_variable_len_value::<TokenMetadata>()
+            .unwrap(),
+        state
+            .get_first_variable_len_value::<FieldHistories>()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn success_record_and_revert() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let original_name = "MySuperCoolToken".to_string();
+    let token_metadata = TokenMetadata {
+        name: original_name.clone(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    // fund the account for the history entries
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let configure_ix = configure_field_history(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Name,
+        2,
+    );
+    process_instruction(&mut context, configure_ix, &update_authority)
+        .await
+        .unwrap();
+
+    for name in ["A", "B", "C"] {
+        let update_ix = update_field(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            Field::Name,
+            name.to_string(),
+        );
+        process_instruction(&mut context, update_ix, &update_authority)
+            .await
+            .unwrap();
+    }
+
+    // only the last two previous values are kept
+    let (fetched_metadata, fetched_histories) = fetch_state(&mut context, &metadata_pubkey).await;
+    assert_eq!(fetched_metadata.name, "C");
+    assert_eq!(
+        fetched_histories,
+        FieldHistories {
+            histories: vec![FieldHistory {
+                field: Field::Name,
+                max_versions: 2,
+                values: vec!["B".to_string(), "A".to_string()],
+            }],
+        }
+    );
+
+    // revert to "A", the current value goes into the history
+    let revert_ix = revert_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Name,
+        1,
+    );
+    process_instruction(&mut context, revert_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let (fetched_metadata, fetched_histories) = fetch_state(&mut context, &metadata_pubkey).await;
+    assert_eq!(fetched_metadata.name, "A");
+    assert_eq!(
+        fetched_histories.histories[0].values,
+        vec!["C".to_string(), "B".to_string()]
+    );
+
+    // untracked fields don't get a history
+    let update_ix = update_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Symbol,
+        "NEW".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &update_authority)
+        .await
+        .unwrap();
+    let (_, fetched_histories) = fetch_state(&mut context, &metadata_pubkey).await;
+    assert_eq!(fetched_histories.histories.len(), 1);
+}
+
+#[tokio::test]
+async fn fail_revert() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    // no history configured
+    let revert_ix = revert_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Name,
+        0,
+    );
+    let error = process_instruction(&mut context, revert_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::FieldHistoryNotFound as u32)
+        )
+    );
+
+    let configure_ix = configure_field_history(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Name,
+        3,
+    );
+    process_instruction(&mut context, configure_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // version out of range
+    let revert_ix = revert_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Name,
+        1,
+    );
+    let error = process_instruction(&mut context, revert_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::FieldVersionNotFound as u32)
+        )
+    );
+
+    // neither update authority nor field authority
+    let other = Keypair::new();
+    let revert_ix = revert_field(
+        &program_id,
+        &metadata_pubkey,
+        &other.pubkey(),
+        Field::Name,
+        0,
+    );
+    let error = process_instruction(&mut context, revert_ix, &other)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldAuthority as u32)
+        )
+    );
+}
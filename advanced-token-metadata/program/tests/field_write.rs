@@ -0,0 +1,353 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError,
+        instruction::{
+            abort_field_write, begin_field_write, commit_field_write, write_field_chunk,
+        },
+        state::FIELD_WRITE_EXPIRY_SECONDS,
+    },
+    field_authority_interface::{
+        errors::FieldAuthorityError, instructions_v2::initialize_field_authorities,
+        state_v2::FieldAuthority,
+    },
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::{tokio, ProgramTestContext},
+    solana_sdk::{
+        clock::Clock, instruction::InstructionError, native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey, signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+const CHUNK_SIZE: usize = 900;
+
+async fn setup_funded_metadata(
+    program_id: &Pubkey,
+    update_authority: &Keypair,
+) -> (tokio::sync::OwnedMutexGuard<ProgramTestContext>, Pubkey) {
+    let (context, client, payer) = setup(program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock_owned().await;
+
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    // fund the account for the staged data and the larger value
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    (context, metadata_pubkey)
+}
+
+#[tokio::test]
+async fn success_chunked_write() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let (mut context, metadata_pubkey) =
+        setup_funded_metadata(&program_id, &update_authority).await;
+
+    let field = Field::Key("image".to_string());
+    let value = format!("<svg>{}</svg>", "<rect/>".repeat(500));
+    assert!(value.len() > 3 * CHUNK_SIZE);
+
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+    );
+    process_instruction(&mut context, begin_ix, &update_authority)
+        .await
+        .unwrap();
+
+    for (i, chunk) in value.as_bytes().chunks(CHUNK_SIZE).enumerate() {
+        let write_ix = write_field_chunk(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            (i * CHUNK_SIZE) as u32,
+            chunk.to_vec(),
+        );
+        process_instruction(&mut context, write_ix, &update_authority)
+            .await
+            .unwrap();
+    }
+
+    let commit_ix = commit_field_write(&program_id, &metadata_pubkey, &update_authority.pubkey());
+    process_instruction(&mut context, commit_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(
+        fetched_metadata.additional_metadata,
+        vec![("image".to_string(), value)]
+    );
+
+    // the write is done, so it can't be committed again
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let commit_ix = commit_field_write(&program_id, &metadata_pubkey, &update_authority.pubkey());
+    let error = process_instruction(&mut context, commit_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::NoFieldWriteInProgress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_chunked_write() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let (mut context, metadata_pubkey) =
+        setup_funded_metadata(&program_id, &update_authority).await;
+
+    // not authorized for the field
+    let other = Keypair::new();
+    let begin_ix = begin_field_write(&program_id, &metadata_pubkey, &other.pubkey(), Field::Uri);
+    let error = process_instruction(&mut context, begin_ix, &other)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldAuthority as u32)
+        )
+    );
+
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Uri,
+    );
+    process_instruction(&mut context, begin_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // only the authority that began the write can write chunks
+    let write_ix = write_field_chunk(
+        &program_id,
+        &metadata_pubkey,
+        &other.pubkey(),
+        0,
+        b"https://".to_vec(),
+    );
+    let error = process_instruction(&mut context, write_ix, &other)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldAuthority as u32)
+        )
+    );
+
+    // chunks can't leave gaps
+    let write_ix = write_field_chunk(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        1,
+        b"https://".to_vec(),
+    );
+    let error = process_instruction(&mut context, write_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::InvalidChunkOffset as u32)
+        )
+    );
+
+    // the staged value must be valid UTF-8
+    let write_ix = write_field_chunk(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        0,
+        vec![0xff, 0xfe],
+    );
+    process_instruction(&mut context, write_ix, &update_authority)
+        .await
+        .unwrap();
+    let commit_ix = commit_field_write(&program_id, &metadata_pubkey, &update_authority.pubkey());
+    let error = process_instruction(&mut context, commit_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::InvalidFieldValue as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_and_fail_contested_write() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let (mut context, metadata_pubkey) =
+        setup_funded_metadata(&program_id, &update_authority).await;
+
+    let field = Field::Key("image".to_string());
+    let field_authority = Keypair::new();
+    let initialize_field_authorities_ix = initialize_field_authorities(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        vec![FieldAuthority {
+            field: field.clone(),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(
+        &mut context,
+        initialize_field_authorities_ix,
+        &update_authority,
+    )
+    .await
+    .unwrap();
+
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field.clone(),
+    );
+    process_instruction(&mut context, begin_ix, &field_authority)
+        .await
+        .unwrap();
+
+    // another authority can't replace the write in progress
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+    );
+    let error = process_instruction(&mut context, begin_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::FieldWriteInProgress as u32)
+        )
+    );
+
+    // nor abort it, unless it's the update authority
+    let other = Keypair::new();
+    let abort_ix = abort_field_write(&program_id, &metadata_pubkey, &other.pubkey());
+    process_instruction(&mut context, abort_ix, &other)
+        .await
+        .unwrap_err();
+
+    let abort_ix = abort_field_write(&program_id, &metadata_pubkey, &update_authority.pubkey());
+    process_instruction(&mut context, abort_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let write_ix = write_field_chunk(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        0,
+        b"<svg>".to_vec(),
+    );
+    let error = process_instruction(&mut context, write_ix, &field_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::NoFieldWriteInProgress as u32)
+        )
+    );
+
+    // an expired write can be taken over
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field.clone(),
+    );
+    process_instruction(&mut context, begin_ix, &field_authority)
+        .await
+        .unwrap();
+
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += FIELD_WRITE_EXPIRY_SECONDS;
+    context.set_sysvar(&clock);
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let begin_ix = begin_field_write(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field,
+    );
+    process_instruction(&mut context, begin_ix, &update_authority)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{error::AdvancedTokenMetadataError, instruction::initialize_strict},
+    program_test::setup,
+    solana_program_test::{tokio, ProgramTestContext},
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        system_instruction,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_client::token::{ExtensionInitializationParams, Token},
+    spl_token_metadata_interface::state::TokenMetadata,
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+/// Creates the metadata account and initializes it strictly
+async fn initialize_strict_metadata(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    token_metadata: &TokenMetadata,
+    metadata_keypair: &Keypair,
+    mint_authority: &Keypair,
+) -> Result<(), TransactionError> {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = token_metadata.tlv_size_of().unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &metadata_keypair.pubkey(),
+                rent.minimum_balance(space),
+                space.try_into().unwrap(),
+                program_id,
+            ),
+            initialize_strict(
+                program_id,
+                &metadata_keypair.pubkey(),
+                &Option::<Pubkey>::from(token_metadata.update_authority).unwrap(),
+                &token_metadata.mint,
+                &mint_authority.pubkey(),
+                token_metadata.name.clone(),
+                token_metadata.symbol.clone(),
+                token_metadata.uri.clone(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, metadata_keypair, mint_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+#[tokio::test]
+async fn success_initialize_strict() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    // a Token-2022 mint pointing to the metadata account
+    let mint_account = Keypair::new();
+    let mint = mint_account.pubkey();
+    let token = Token::new(client, &spl_token_2022::id(), &mint, Some(0), payer);
+    token
+        .create_mint(
+            &mint_authority.pubkey(),
+            None,
+            vec![ExtensionInitializationParams::MetadataPointer {
+                authority: None,
+                metadata_address: Some(metadata_pubkey),
+            }],
+            &[&mint_account],
+        )
+        .await
+        .unwrap();
+
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(Pubkey::new_unique()).try_into().unwrap(),
+        mint,
+        ..Default::default()
+    };
+
+    let mut context = context.lock().await;
+    initialize_strict_metadata(
+        &mut context,
+        &program_id,
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await
+    .unwrap();
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(fetched_metadata, token_metadata);
+}
+
+#[tokio::test]
+async fn fail_metadata_pointer_mismatch() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+
+    // pointing elsewhere, and without a pointer at all
+    let extensions = [
+        vec![ExtensionInitializationParams::MetadataPointer {
+            authority: None,
+            metadata_address: Some(Pubkey::new_unique()),
+        }],
+        vec![],
+    ];
+    for extension_initialization_params in extensions {
+        let mint_account = Keypair::new();
+        let mint = mint_account.pubkey();
+        let token = Token::new(
+            client.clone(),
+            &spl_token_2022::id(),
+            &mint,
+            Some(0),
+            payer.clone(),
+        );
+        token
+            .create_mint(
+                &mint_authority.pubkey(),
+                None,
+                extension_initialization_params,
+                &[&mint_account],
+            )
+            .await
+            .unwrap();
+
+        let token_metadata = TokenMetadata {
+            name: "MySuperCoolToken".to_string(),
+            symbol: "MINE".to_string(),
+            uri: "my.super.cool.token".to_string(),
+            update_authority: Some(Pubkey::new_unique()).try_into().unwrap(),
+            mint,
+            ..Default::default()
+        };
+
+        let mut context = context.lock().await;
+        let error = initialize_strict_metadata(
+            &mut context,
+            &program_id,
+            &token_metadata,
+            &Keypair::new(),
+            &mint_authority,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(
+                    AdvancedTokenMetadataError::MetadataPointerMismatch as u32
+                )
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn fail_mint_not_owned_by_token_program() {
+    let program_id = Pubkey::new_unique();
+    let (context, _client, _payer) = setup(&program_id).await;
+    let mut context = context.lock().await;
+
+    let mint_authority = Keypair::new();
+
+    // any account can pose as a mint to the plain initialize, but not to the strict one
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(Pubkey::new_unique()).try_into().unwrap(),
+        mint: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    let error = initialize_strict_metadata(
+        &mut context,
+        &program_id,
+        &token_metadata,
+        &Keypair::new(),
+        &mint_authority,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(1, InstructionError::IllegalOwner)
+    );
+}
@@ -0,0 +1,364 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::error::AdvancedTokenMetadataError,
+    field_authority_interface::{
+        find_legacy_field_pda,
+        instructions::{remove_field_authority_for_pda, update_field_with_field_authority_for_pda},
+        instructions_v2::{remove_field_authority_v2, update_field_with_field_authority_v2},
+        normalize_key,
+        state::FieldAuthorityAccount,
+        state_v2::{FieldAuthorities, FieldAuthority},
+    },
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::{tokio, ProgramTestContext},
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        instruction::InstructionError,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::{
+        instruction::{remove_key, update_field},
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed, TlvStateMut},
+};
+
+/// Shared with the JS client, which has to normalize keys byte for byte like the program
+const NORMALIZE_KEY_VECTORS: &str =
+    include_str!("../../../field-authority-interface/test/normalize-key-vectors.json");
+
+#[test]
+fn normalize_key_vectors() {
+    let vectors: serde_json::Value = serde_json::from_str(NORMALIZE_KEY_VECTORS).unwrap();
+    for vector in vectors.as_array().unwrap() {
+        let input = vector["input"].as_str().unwrap();
+        let expected = vector["expected"].as_str().unwrap();
+        assert_eq!(normalize_key(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Overwrites the metadata account, to recreate entries written before keys were normalized
+async fn set_legacy_metadata(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    token_metadata: &TokenMetadata,
+    field_authorities: &FieldAuthorities,
+) {
+    let space = token_metadata.tlv_size_of().unwrap() + field_authorities.tlv_size_of().unwrap();
+    let mut data = vec![0; space];
+    let mut state = TlvStateMut::unpack(&mut data).unwrap();
+    state
+        .alloc_and_pack_variable_len_entry(token_metadata, false)
+        .unwrap();
+    state
+        .alloc_and_pack_variable_len_entry(field_authorities, false)
+        .unwrap();
+    context.set_account(
+        metadata,
+        &AccountSharedData::from(Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: *program_id,
+            ..Account::default()
+        }),
+    );
+}
+
+/// Reads the metadata stored in the metadata account
+async fn get_token_metadata(context: &mut ProgramTestContext, metadata: &Pubkey) -> TokenMetadata {
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(*metadata)
+        .await
+        .unwrap()
+        .unwrap();
+    TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn success_and_fail_normalized_keys() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    // differently written keys end up as the same key
+    for (key, value) in [(" Nickname ", "first"), ("NICKNAME", "second")] {
+        let update_ix = update_field(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            Field::Key(key.to_string()),
+            value.to_string(),
+        );
+        process_instruction(&mut context, update_ix, &update_authority)
+            .await
+            .unwrap();
+    }
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata_state = TlvStateBorrowed::unpack(&fetched_metadata_account.data).unwrap();
+    let fetched_metadata = fetched_metadata_state
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(
+        fetched_metadata.additional_metadata,
+        vec![("nickname".to_string(), "second".to_string())]
+    );
+
+    // the reserved namespace can't be written, whatever the case
+    let update_ix = update_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Key("SYS:verified".to_string()),
+        "true".to_string(),
+    );
+    let error = process_instruction(&mut context, update_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::ReservedKey as u32)
+        )
+    );
+
+    // removal uses the normalized key too
+    let remove_ix = remove_key(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        " NickName".to_string(),
+        false,
+    );
+    process_instruction(&mut context, remove_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata_state = TlvStateBorrowed::unpack(&fetched_metadata_account.data).unwrap();
+    let fetched_metadata = fetched_metadata_state
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert!(fetched_metadata.additional_metadata.is_empty());
+}
+
+#[tokio::test]
+async fn success_legacy_keys() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let mut token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    // a value and a field authority stored under the key as written
+    let legacy_field = Field::Key("Nickname".to_string());
+    token_metadata
+        .additional_metadata
+        .push(("Nickname".to_string(), "Zorg".to_string()));
+    let legacy_field_authority = FieldAuthority {
+        field: legacy_field.clone(),
+        authority: field_authority.pubkey(),
+    };
+    set_legacy_metadata(
+        &mut context,
+        &program_id,
+        &metadata_pubkey,
+        &token_metadata,
+        &FieldAuthorities {
+            authorities: vec![legacy_field_authority.clone()],
+        },
+    )
+    .await;
+
+    // the field authority is reachable through the normalized key, and the legacy value is
+    // replaced instead of duplicated
+    let update_ix = update_field_with_field_authority_v2(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        Field::Key("nickname".to_string()),
+        "Zorg II".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+    let fetched_metadata = get_token_metadata(&mut context, &metadata_pubkey).await;
+    assert_eq!(
+        fetched_metadata.additional_metadata,
+        vec![("nickname".to_string(), "Zorg II".to_string())]
+    );
+
+    // and the legacy entry can be removed
+    let remove_ix = remove_field_authority_v2(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        legacy_field_authority,
+        false,
+    );
+    process_instruction(&mut context, remove_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let update_ix = update_field_with_field_authority_v2(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        legacy_field.clone(),
+        "Zorg III".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap_err();
+
+    // a v1 field PDA derived from the key as written
+    let (legacy_field_pda, _) =
+        find_legacy_field_pda(&program_id, &metadata_pubkey, legacy_field.clone());
+    context.set_account(
+        &legacy_field_pda,
+        &AccountSharedData::from(Account {
+            lamports: LAMPORTS_PER_SOL,
+            data: borsh::to_vec(&FieldAuthorityAccount {
+                authority: field_authority.pubkey(),
+            })
+            .unwrap(),
+            owner: program_id,
+            ..Account::default()
+        }),
+    );
+
+    let update_ix = update_field_with_field_authority_for_pda(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        &legacy_field_pda,
+        legacy_field.clone(),
+        "Zorg III".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+    let fetched_metadata = get_token_metadata(&mut context, &metadata_pubkey).await;
+    assert_eq!(
+        fetched_metadata.additional_metadata,
+        vec![("nickname".to_string(), "Zorg III".to_string())]
+    );
+
+    let remove_ix = remove_field_authority_for_pda(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        &legacy_field_pda,
+        legacy_field,
+    );
+    process_instruction(&mut context, remove_ix, &update_authority)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(legacy_field_pda)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError,
+        instruction::set_key_schema,
+        state::{KeySchema, KeySchemaEntry, KeyValueType},
+    },
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::tokio,
+    solana_sdk::{
+        instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::{
+        instruction::update_field,
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+    test_case::test_case,
+};
+
+fn key_schema_entries() -> Vec<KeySchemaEntry> {
+    vec![
+        KeySchemaEntry {
+            key: "nickname".to_string(),
+            value_type: KeyValueType::String,
+        },
+        KeySchemaEntry {
+            key: "level".to_string(),
+            value_type: KeyValueType::Integer,
+        },
+        KeySchemaEntry {
+            key: "verified".to_string(),
+            value_type: KeyValueType::Bool,
+        },
+        KeySchemaEntry {
+            key: "owner".to_string(),
+            value_type: KeyValueType::Pubkey,
+        },
+        KeySchemaEntry {
+            key: "site".to_string(),
+            value_type: KeyValueType::Url,
+        },
+    ]
+}
+
+#[test_case(Field::Name, "New name".to_string(), None ; "name")]
+#[test_case(Field::Key("nickname".to_string()), "anything goes".to_string(), None ; "string")]
+#[test_case(Field::Key("level".to_string()), "-42".to_string(), None ; "integer")]
+#[test_case(Field::Key("level".to_string()), "4.2".to_string(), Some(AdvancedTokenMetadataError::InvalidValueType) ; "bad integer")]
+#[test_case(Field::Key("verified".to_string()), "true".to_string(), None ; "bool")]
+#[test_case(Field::Key("verified".to_string()), "yes".to_string(), Some(AdvancedTokenMetadataError::InvalidValueType) ; "bad bool")]
+#[test_case(Field::Key("owner".to_string()), Pubkey::new_unique().to_string(), None ; "pubkey")]
+#[test_case(Field::Key("owner".to_string()), "not a pubkey".to_string(), Some(AdvancedTokenMetadataError::InvalidValueType) ; "bad pubkey")]
+#[test_case(Field::Key("site".to_string()), "https://gardenlabs.com".to_string(), None ; "url")]
+#[test_case(Field::Key("site".to_string()), "gardenlabs.com".to_string(), Some(AdvancedTokenMetadataError::InvalidValueType) ; "bad url")]
+#[test_case(Field::Key("level".to_string()), "".to_string(), None ; "cleared integer")]
+#[test_case(Field::Key("site".to_string()), "".to_string(), None ; "cleared url")]
+#[test_case(Field::Key("nickame".to_string()), "typo".to_string(), Some(AdvancedTokenMetadataError::UnknownKey) ; "unknown key")]
+#[tokio::test]
+async fn update_with_schema(
+    field: Field,
+    value: String,
+    expected_error: Option<AdvancedTokenMetadataError>,
+) {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let mut token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    // fund the account for the schema entry
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let set_schema_ix = set_key_schema(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        key_schema_entries(),
+    );
+    process_instruction(&mut context, set_schema_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let update_ix = update_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        field.clone(),
+        value.clone(),
+    );
+    let result = process_instruction(&mut context, update_ix, &update_authority).await;
+
+    match expected_error {
+        Some(error) => assert_eq!(
+            result.unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+        ),
+        None => {
+            result.unwrap();
+
+            // check that the data is correct
+            token_metadata.update(field, value);
+            let fetched_metadata_account = context
+                .banks_client
+                .get_account(metadata_pubkey)
+                .await
+                .unwrap()
+                .unwrap();
+            let fetched_metadata_state =
+                TlvStateBorrowed::unpack(&fetched_metadata_account.data).unwrap();
+            let fetched_metadata = fetched_metadata_state
+                .get_first_variable_len_value::<TokenMetadata>()
+                .unwrap();
+            assert_eq!(fetched_metadata, token_metadata);
+            let fetched_schema = fetched_metadata_state
+                .get_first_variable_len_value::<KeySchema>()
+                .unwrap();
+            assert_eq!(fetched_schema.entries, key_schema_entries());
+        }
+    }
+}
@@ -3,8 +3,12 @@
 use {
     solana_program_test::{processor, tokio::sync::Mutex, ProgramTest, ProgramTestContext},
     solana_sdk::{
-        pubkey::Pubkey, signature::Signer, signer::keypair::Keypair, system_instruction,
-        transaction::Transaction,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        system_instruction,
+        transaction::{Transaction, TransactionError},
     },
     spl_token_client::{
         client::{
@@ -165,3 +169,22 @@ pub async fn setup_update_field(
         .await
         .unwrap();
 }
+
+#[allow(dead_code)]
+pub async fn process_instruction(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signer: &Keypair,
+) -> Result<(), TransactionError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, signer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
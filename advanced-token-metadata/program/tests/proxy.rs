@@ -0,0 +1,513 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError,
+        instruction::{
+            configure_field_history, initialize_proxy, reclaim_proxy_authority, set_key_schema,
+            update_field_with_proxy,
+        },
+        state::{
+            find_proxy_authority_address, FieldHistories, FieldHistory, KeySchemaEntry,
+            KeyValueType, ProxyAuthority,
+        },
+    },
+    field_authority_interface::{
+        errors::FieldAuthorityError,
+        instructions_v2::add_field_authority_v2,
+        state::{FieldAuthorities, FieldAuthority},
+    },
+    program_test::{process_instruction, setup},
+    solana_program_test::{tokio, ProgramTestContext},
+    solana_sdk::{
+        instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_2022::{
+        extension::{BaseStateWithExtensions, StateWithExtensions},
+        state::Mint,
+    },
+    spl_token_client::token::{ExtensionInitializationParams, Token},
+    spl_token_metadata_interface::{
+        error::TokenMetadataError,
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+/// Creates a Token-2022 mint storing its own metadata, funded for larger values
+async fn setup_embedded_metadata(
+    program_id: &Pubkey,
+    update_authority: &Keypair,
+) -> (tokio::sync::OwnedMutexGuard<ProgramTestContext>, Pubkey) {
+    let (context, client, payer) = setup(program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_account = Keypair::new();
+    let mint = mint_account.pubkey();
+    let token = Token::new(client, &spl_token_2022::id(), &mint, Some(0), payer.clone());
+    token
+        .create_mint(
+            &mint_authority.pubkey(),
+            None,
+            vec![ExtensionInitializationParams::MetadataPointer {
+                authority: None,
+                metadata_address: Some(mint),
+            }],
+            &[&mint_account],
+        )
+        .await
+        .unwrap();
+    token
+        .token_metadata_initialize_with_rent_transfer(
+            &payer.pubkey(),
+            &update_authority.pubkey(),
+            &mint_authority.pubkey(),
+            "MySuperCoolToken".to_string(),
+            "MINE".to_string(),
+            "my.super.cool.token".to_string(),
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    let mut context = context.lock_owned().await;
+
+    // fund the mint for the larger values, Token-2022 doesn't top up rent itself
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &mint, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    (context, mint)
+}
+
+async fn get_embedded_metadata(context: &mut ProgramTestContext, mint: Pubkey) -> TokenMetadata {
+    let mint_account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .unwrap()
+        .get_variable_len_extension::<TokenMetadata>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn success_update_through_proxy() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let (mut context, mint) = setup_embedded_metadata(&program_id, &update_authority).await;
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+
+    let nickname = Field::Key("nickname".to_string());
+    let authorities = vec![FieldAuthority {
+        field: nickname.clone(),
+        authority: field_authority.pubkey(),
+    }];
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        authorities.clone(),
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // the proxy took over the embedded metadata
+    let token_metadata = get_embedded_metadata(&mut context, mint).await;
+    assert_eq!(
+        Option::<Pubkey>::from(token_metadata.update_authority),
+        Some(proxy)
+    );
+
+    let proxy_account = context
+        .banks_client
+        .get_account(proxy)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(proxy_account.owner, program_id);
+    let state = TlvStateBorrowed::unpack(&proxy_account.data).unwrap();
+    assert_eq!(
+        state
+            .get_first_variable_len_value::<ProxyAuthority>()
+            .unwrap(),
+        ProxyAuthority {
+            mint,
+            update_authority: Some(update_authority.pubkey()),
+        }
+    );
+    assert_eq!(
+        state
+            .get_first_variable_len_value::<FieldAuthorities>()
+            .unwrap(),
+        FieldAuthorities { authorities }
+    );
+
+    // the field authority updates its field, the update authority anything
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &field_authority.pubkey(),
+        nickname.clone(),
+        "Zorg".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &update_authority.pubkey(),
+        Field::Name,
+        "MyEvenCoolerToken".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let token_metadata = get_embedded_metadata(&mut context, mint).await;
+    assert_eq!(token_metadata.name, "MyEvenCoolerToken");
+    assert_eq!(
+        token_metadata.additional_metadata,
+        vec![("nickname".to_string(), "Zorg".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn success_add_field_authority_to_proxy() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let (mut context, mint) = setup_embedded_metadata(&program_id, &update_authority).await;
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+
+    // the proxy account is managed like a metadata account, so it needs rent for the growth.
+    // Funding it before it's initialized doesn't stop it from being created.
+    let fund_ix = system_instruction::transfer(&context.payer.pubkey(), &proxy, LAMPORTS_PER_SOL);
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        vec![],
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let field = Field::Key("nickname".to_string());
+    let add_ix = add_field_authority_v2(
+        &program_id,
+        &proxy,
+        &update_authority.pubkey(),
+        FieldAuthority {
+            field: field.clone(),
+            authority: field_authority.pubkey(),
+        },
+        false,
+    );
+    process_instruction(&mut context, add_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &field_authority.pubkey(),
+        field,
+        "Zorg".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+
+    let token_metadata = get_embedded_metadata(&mut context, mint).await;
+    assert_eq!(
+        token_metadata.additional_metadata,
+        vec![("nickname".to_string(), "Zorg".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn fail_update_through_proxy() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let (mut context, mint) = setup_embedded_metadata(&program_id, &update_authority).await;
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+
+    // not the proxy PDA of the mint
+    let wrong_proxy = Pubkey::new_unique();
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &wrong_proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        vec![],
+    );
+    let error = process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::IncorrectProxyAuthority as u32)
+        )
+    );
+
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        vec![FieldAuthority {
+            field: Field::Key("nickname".to_string()),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // field authorities can't update other fields
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &field_authority.pubkey(),
+        Field::Name,
+        "Zorg".to_string(),
+    );
+    let error = process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_key_schema_and_history_through_proxy() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let (mut context, mint) = setup_embedded_metadata(&program_id, &update_authority).await;
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        vec![],
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // the schema and history live in the proxy account, which needs rent for them
+    let fund_ix = system_instruction::transfer(&context.payer.pubkey(), &proxy, LAMPORTS_PER_SOL);
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let level = Field::Key("level".to_string());
+    let schema_ix = set_key_schema(
+        &program_id,
+        &proxy,
+        &update_authority.pubkey(),
+        vec![KeySchemaEntry {
+            key: "level".to_string(),
+            value_type: KeyValueType::Integer,
+        }],
+    );
+    process_instruction(&mut context, schema_ix, &update_authority)
+        .await
+        .unwrap();
+    let history_ix = configure_field_history(
+        &program_id,
+        &proxy,
+        &update_authority.pubkey(),
+        level.clone(),
+        2,
+    );
+    process_instruction(&mut context, history_ix, &update_authority)
+        .await
+        .unwrap();
+
+    for value in ["1", "2"] {
+        let update_ix = update_field_with_proxy(
+            &program_id,
+            &mint,
+            &proxy,
+            &update_authority.pubkey(),
+            level.clone(),
+            value.to_string(),
+        );
+        process_instruction(&mut context, update_ix, &update_authority)
+            .await
+            .unwrap();
+    }
+
+    // updates are checked against the schema
+    for (field, value, expected) in [
+        (
+            level.clone(),
+            "high",
+            AdvancedTokenMetadataError::InvalidValueType,
+        ),
+        (
+            Field::Key("nickname".to_string()),
+            "Zorg",
+            AdvancedTokenMetadataError::UnknownKey,
+        ),
+    ] {
+        let update_ix = update_field_with_proxy(
+            &program_id,
+            &mint,
+            &proxy,
+            &update_authority.pubkey(),
+            field,
+            value.to_string(),
+        );
+        let error = process_instruction(&mut context, update_ix, &update_authority)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(0, InstructionError::Custom(expected as u32))
+        );
+    }
+
+    let token_metadata = get_embedded_metadata(&mut context, mint).await;
+    assert_eq!(
+        token_metadata.additional_metadata,
+        vec![("level".to_string(), "2".to_string())]
+    );
+
+    let proxy_account = context
+        .banks_client
+        .get_account(proxy)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = TlvStateBorrowed::unpack(&proxy_account.data).unwrap();
+    assert_eq!(
+        state
+            .get_first_variable_len_value::<FieldHistories>()
+            .unwrap(),
+        FieldHistories {
+            histories: vec![FieldHistory {
+                field: level,
+                max_versions: 2,
+                values: vec!["1".to_string()],
+            }],
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_reclaim_proxy_authority() {
+    let program_id = Pubkey::new_unique();
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let (mut context, mint) = setup_embedded_metadata(&program_id, &update_authority).await;
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &context.payer.pubkey(),
+        vec![FieldAuthority {
+            field: Field::Key("nickname".to_string()),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // field authorities can't take over the metadata
+    let destination = Pubkey::new_unique();
+    let reclaim_ix = reclaim_proxy_authority(
+        &program_id,
+        &mint,
+        &proxy,
+        &field_authority.pubkey(),
+        &destination,
+        Some(field_authority.pubkey()),
+    );
+    let error = process_instruction(&mut context, reclaim_ix, &field_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenMetadataError::IncorrectUpdateAuthority as u32)
+        )
+    );
+
+    // the previous update authority hands the metadata to another key
+    let new_update_authority = Pubkey::new_unique();
+    let proxy_lamports = context.banks_client.get_balance(proxy).await.unwrap();
+    let reclaim_ix = reclaim_proxy_authority(
+        &program_id,
+        &mint,
+        &proxy,
+        &update_authority.pubkey(),
+        &destination,
+        Some(new_update_authority),
+    );
+    process_instruction(&mut context, reclaim_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let token_metadata = get_embedded_metadata(&mut context, mint).await;
+    assert_eq!(
+        Option::<Pubkey>::from(token_metadata.update_authority),
+        Some(new_update_authority)
+    );
+
+    // the proxy account is closed, so the mint can be proxied again
+    assert!(context
+        .banks_client
+        .get_account(proxy)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        proxy_lamports
+    );
+}
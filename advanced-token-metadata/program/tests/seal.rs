@@ -0,0 +1,362 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError,
+        instruction::{
+            initialize_proxy, reclaim_proxy_authority, seal_metadata, update_field_with_proxy,
+        },
+        state::find_proxy_authority_address,
+    },
+    field_authority_interface::{
+        errors::FieldAuthorityError,
+        instructions_v2::{
+            add_field_authority_v2, initialize_field_authorities, remove_field_authority_v2,
+            update_field_with_field_authority_v2,
+        },
+        state::FieldAuthority,
+    },
+    program_test::{process_instruction, setup, setup_metadata, setup_mint},
+    solana_program_test::tokio,
+    solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_2022::{
+        extension::{BaseStateWithExtensions, StateWithExtensions},
+        state::Mint,
+    },
+    spl_token_client::token::{ExtensionInitializationParams, Token},
+    spl_token_metadata_interface::{
+        instruction::{self, remove_key, update_field},
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+fn sealed_error() -> TransactionError {
+    TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(AdvancedTokenMetadataError::MetadataSealed as u32),
+    )
+}
+
+#[tokio::test]
+async fn success_seal_metadata() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+
+    let token_program_id = spl_token_2022::id();
+    let decimals = 2;
+    let token = setup_mint(
+        &token_program_id,
+        &mint_authority_pubkey,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let mut context = context.lock().await;
+
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    let mut token_metadata = TokenMetadata {
+        name: "MySuperCoolToken".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.token".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: *token.get_address(),
+        ..Default::default()
+    };
+
+    let metadata_keypair = Keypair::new();
+    let metadata_pubkey = metadata_keypair.pubkey();
+
+    setup_metadata(
+        &mut context,
+        &program_id,
+        token.get_address(),
+        &token_metadata,
+        &metadata_keypair,
+        &mint_authority,
+    )
+    .await;
+
+    // fund the account for the field authorities, the seal and the new values
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let nickname = Field::Key("nickname".to_string());
+    let initialize_field_authorities_ix = initialize_field_authorities(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        vec![FieldAuthority {
+            field: nickname.clone(),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(
+        &mut context,
+        initialize_field_authorities_ix,
+        &update_authority,
+    )
+    .await
+    .unwrap();
+
+    let seal_ix = seal_metadata(&program_id, &metadata_pubkey, &update_authority.pubkey());
+    process_instruction(&mut context, seal_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // the field authority keeps updating its field
+    let update_ix = update_field_with_field_authority_v2(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        nickname.clone(),
+        "Zorg".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+    token_metadata.update(nickname.clone(), "Zorg".to_string());
+
+    // the update authority can't write or manage field authorities anymore
+    let other_authority = FieldAuthority {
+        field: Field::Name,
+        authority: update_authority.pubkey(),
+    };
+    let instructions: Vec<Instruction> = vec![
+        update_field(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            Field::Name,
+            "MyEvenCoolerToken".to_string(),
+        ),
+        update_field(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            nickname.clone(),
+            "Zorg II".to_string(),
+        ),
+        remove_key(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            "nickname".to_string(),
+            false,
+        ),
+        add_field_authority_v2(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            other_authority,
+            false,
+        ),
+        remove_field_authority_v2(
+            &program_id,
+            &metadata_pubkey,
+            &update_authority.pubkey(),
+            FieldAuthority {
+                field: nickname,
+                authority: field_authority.pubkey(),
+            },
+            false,
+        ),
+        seal_metadata(&program_id, &metadata_pubkey, &update_authority.pubkey()),
+    ];
+    // sealing again would otherwise repeat the first seal transaction
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    for instruction in instructions {
+        let error = process_instruction(&mut context, instruction, &update_authority)
+            .await
+            .unwrap_err();
+        assert_eq!(error, sealed_error());
+    }
+
+    // handing over the update authority doesn't unseal the metadata
+    let new_update_authority = Keypair::new();
+    let update_authority_ix = instruction::update_authority(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Some(new_update_authority.pubkey()).try_into().unwrap(),
+    );
+    process_instruction(&mut context, update_authority_ix, &update_authority)
+        .await
+        .unwrap();
+    token_metadata.update_authority = Some(new_update_authority.pubkey()).try_into().unwrap();
+
+    let update_ix = update_field(
+        &program_id,
+        &metadata_pubkey,
+        &new_update_authority.pubkey(),
+        Field::Name,
+        "MyEvenCoolerToken".to_string(),
+    );
+    let error = process_instruction(&mut context, update_ix, &new_update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(error, sealed_error());
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(fetched_metadata, token_metadata);
+}
+
+#[tokio::test]
+async fn success_seal_proxy() {
+    let program_id = Pubkey::new_unique();
+    let (context, client, payer) = setup(&program_id).await;
+
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+
+    // a Token-2022 mint storing its own metadata
+    let mint_authority = Keypair::new();
+    let mint_account = Keypair::new();
+    let mint = mint_account.pubkey();
+    let token = Token::new(client, &spl_token_2022::id(), &mint, Some(0), payer.clone());
+    token
+        .create_mint(
+            &mint_authority.pubkey(),
+            None,
+            vec![ExtensionInitializationParams::MetadataPointer {
+                authority: None,
+                metadata_address: Some(mint),
+            }],
+            &[&mint_account],
+        )
+        .await
+        .unwrap();
+    token
+        .token_metadata_initialize_with_rent_transfer(
+            &payer.pubkey(),
+            &update_authority.pubkey(),
+            &mint_authority.pubkey(),
+            "MySuperCoolToken".to_string(),
+            "MINE".to_string(),
+            "my.super.cool.token".to_string(),
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    let mut context = context.lock().await;
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &mint, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let (proxy, _) = find_proxy_authority_address(&program_id, &mint);
+    let nickname = Field::Key("nickname".to_string());
+    let initialize_ix = initialize_proxy(
+        &program_id,
+        &proxy,
+        &mint,
+        &update_authority.pubkey(),
+        &payer.pubkey(),
+        vec![FieldAuthority {
+            field: nickname.clone(),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(&mut context, initialize_ix, &update_authority)
+        .await
+        .unwrap();
+
+    // the proxy account needs rent for the seal
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &proxy, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let seal_ix = seal_metadata(&program_id, &proxy, &update_authority.pubkey());
+    process_instruction(&mut context, seal_ix, &update_authority)
+        .await
+        .unwrap();
+
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &field_authority.pubkey(),
+        nickname,
+        "Zorg".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+
+    // the previous update authority is now treated like anyone else
+    let update_ix = update_field_with_proxy(
+        &program_id,
+        &mint,
+        &proxy,
+        &update_authority.pubkey(),
+        Field::Name,
+        "MyEvenCoolerToken".to_string(),
+    );
+    let error = process_instruction(&mut context, update_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FieldAuthorityError::IncorrectFieldAuthority as u32)
+        )
+    );
+
+    // nor can it take the embedded metadata back from the proxy
+    let reclaim_ix = reclaim_proxy_authority(
+        &program_id,
+        &mint,
+        &proxy,
+        &update_authority.pubkey(),
+        &update_authority.pubkey(),
+        Some(update_authority.pubkey()),
+    );
+    let error = process_instruction(&mut context, reclaim_ix, &update_authority)
+        .await
+        .unwrap_err();
+    assert_eq!(error, sealed_error());
+
+    let mint_account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let token_metadata = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .unwrap()
+        .get_variable_len_extension::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(token_metadata.name, "MySuperCoolToken");
+    assert_eq!(
+        token_metadata.additional_metadata,
+        vec![("nickname".to_string(), "Zorg".to_string())]
+    );
+}
@@ -0,0 +1,230 @@
+#![cfg(feature = "test-sbf")]
+
+mod program_test;
+use {
+    advanced_token_metadata::{
+        error::AdvancedTokenMetadataError, instruction::initialize_for_subject,
+        state::find_subject_metadata_address,
+    },
+    field_authority_interface::{
+        instructions_v2::{initialize_field_authorities, update_field_with_field_authority_v2},
+        state::FieldAuthority,
+    },
+    program_test::{process_instruction, setup},
+    solana_program_test::tokio,
+    solana_sdk::{
+        instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        signature::Signer, signer::keypair::Keypair, system_instruction,
+        transaction::TransactionError,
+    },
+    spl_token_metadata_interface::{
+        instruction::update_field,
+        state::{Field, TokenMetadata},
+    },
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed},
+};
+
+#[tokio::test]
+async fn success_initialize_for_subject() {
+    let program_id = Pubkey::new_unique();
+    let (context, _client, payer) = setup(&program_id).await;
+    let mut context = context.lock_owned().await;
+
+    let update_authority = Keypair::new();
+    let field_authority = Keypair::new();
+    // e.g. a wallet, rather than a mint
+    let subject = Keypair::new();
+    let (metadata_pubkey, _) = find_subject_metadata_address(&program_id, &subject.pubkey());
+
+    let mut token_metadata = TokenMetadata {
+        name: "MySuperCoolWallet".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.wallet".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: subject.pubkey(),
+        ..Default::default()
+    };
+    let initialize_ix = initialize_for_subject(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        &subject.pubkey(),
+        &payer.pubkey(),
+        token_metadata.name.clone(),
+        token_metadata.symbol.clone(),
+        token_metadata.uri.clone(),
+    );
+    process_instruction(&mut context, initialize_ix, &subject)
+        .await
+        .unwrap();
+
+    // fund the account for the field authorities and the new values
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    // the existing instructions work on the subject's metadata
+    let update_ix = update_field(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        Field::Uri,
+        "my.even.cooler.wallet".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &update_authority)
+        .await
+        .unwrap();
+    token_metadata.update(Field::Uri, "my.even.cooler.wallet".to_string());
+
+    let field = Field::Key("nickname".to_string());
+    let initialize_field_authorities_ix = initialize_field_authorities(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        vec![FieldAuthority {
+            field: field.clone(),
+            authority: field_authority.pubkey(),
+        }],
+    );
+    process_instruction(
+        &mut context,
+        initialize_field_authorities_ix,
+        &update_authority,
+    )
+    .await
+    .unwrap();
+
+    let update_ix = update_field_with_field_authority_v2(
+        &program_id,
+        &metadata_pubkey,
+        &field_authority.pubkey(),
+        field.clone(),
+        "Zorg".to_string(),
+    );
+    process_instruction(&mut context, update_ix, &field_authority)
+        .await
+        .unwrap();
+    token_metadata.update(field, "Zorg".to_string());
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched_metadata_account.owner, program_id);
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(fetched_metadata, token_metadata);
+}
+
+#[tokio::test]
+async fn success_initialize_for_prefunded_subject() {
+    let program_id = Pubkey::new_unique();
+    let (context, _client, payer) = setup(&program_id).await;
+    let mut context = context.lock_owned().await;
+
+    let update_authority = Keypair::new();
+    let subject = Keypair::new();
+    let (metadata_pubkey, _) = find_subject_metadata_address(&program_id, &subject.pubkey());
+
+    // anyone can send lamports to the address before it's initialized
+    let fund_ix = system_instruction::transfer(&payer.pubkey(), &metadata_pubkey, LAMPORTS_PER_SOL);
+    process_instruction(&mut context, fund_ix, &payer)
+        .await
+        .unwrap();
+
+    let token_metadata = TokenMetadata {
+        name: "MySuperCoolWallet".to_string(),
+        symbol: "MINE".to_string(),
+        uri: "my.super.cool.wallet".to_string(),
+        update_authority: Some(update_authority.pubkey()).try_into().unwrap(),
+        mint: subject.pubkey(),
+        ..Default::default()
+    };
+    let initialize_ix = initialize_for_subject(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority.pubkey(),
+        &subject.pubkey(),
+        &payer.pubkey(),
+        token_metadata.name.clone(),
+        token_metadata.symbol.clone(),
+        token_metadata.uri.clone(),
+    );
+    process_instruction(&mut context, initialize_ix, &subject)
+        .await
+        .unwrap();
+
+    let fetched_metadata_account = context
+        .banks_client
+        .get_account(metadata_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched_metadata_account.owner, program_id);
+    assert_eq!(fetched_metadata_account.lamports, LAMPORTS_PER_SOL);
+    let fetched_metadata = TlvStateBorrowed::unpack(&fetched_metadata_account.data)
+        .unwrap()
+        .get_first_variable_len_value::<TokenMetadata>()
+        .unwrap();
+    assert_eq!(fetched_metadata, token_metadata);
+}
+
+#[tokio::test]
+async fn fail_initialize_for_subject() {
+    let program_id = Pubkey::new_unique();
+    let (context, _client, payer) = setup(&program_id).await;
+    let mut context = context.lock_owned().await;
+
+    let update_authority = Pubkey::new_unique();
+    let subject = Keypair::new();
+    let (metadata_pubkey, _) = find_subject_metadata_address(&program_id, &subject.pubkey());
+
+    // the subject must sign
+    let mut initialize_ix = initialize_for_subject(
+        &program_id,
+        &metadata_pubkey,
+        &update_authority,
+        &subject.pubkey(),
+        &payer.pubkey(),
+        "MySuperCoolWallet".to_string(),
+        "MINE".to_string(),
+        "my.super.cool.wallet".to_string(),
+    );
+    initialize_ix.accounts[2].is_signer = false;
+    let error = process_instruction(&mut context, initialize_ix, &payer)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // metadata must be the subject's PDA
+    let wrong_subject = Pubkey::new_unique();
+    let (wrong_metadata_pubkey, _) = find_subject_metadata_address(&program_id, &wrong_subject);
+    let initialize_ix = initialize_for_subject(
+        &program_id,
+        &wrong_metadata_pubkey,
+        &update_authority,
+        &subject.pubkey(),
+        &payer.pubkey(),
+        "MySuperCoolWallet".to_string(),
+        "MINE".to_string(),
+        "my.super.cool.wallet".to_string(),
+    );
+    let error = process_instruction(&mut context, initialize_ix, &subject)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(AdvancedTokenMetadataError::IncorrectSubjectMetadata as u32)
+        )
+    );
+}
@@ -34,6 +34,7 @@ import {
   createUpdateFieldWithFieldAuthorityV2Ix,
   createAddFieldAuthorityV2Ix,
   createRemoveFieldAuthorityV2Ix,
+  createRemoveKeyWithFieldAuthorityV2Ix,
   getSpaceRent,
 } from "../../field-authority-interface/js";
 
@@ -453,6 +454,87 @@ describe("Advanced Token Metadata Program V2", () => {
     }
   });
 
+  async function removeKeyWithFieldAuthorityTest(
+    key: string,
+    fa: Keypair = ANCHOR_WALLET_KEYPAIR
+  ): Promise<void> {
+    const destination = Keypair.generate().publicKey;
+    const metadataBefore = await getConnection().getAccountInfo(
+      metadataKeypair.publicKey
+    );
+    const ix = createRemoveKeyWithFieldAuthorityV2Ix({
+      programId: ATM_PROGRAM_ID,
+      metadata: metadataKeypair.publicKey,
+      fieldAuthority: fa.publicKey,
+      destination,
+      key,
+      idempotent: false,
+    });
+    const tx = new Transaction().add(ix);
+
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+      fa,
+    ]);
+
+    const vals = {
+      ...metadataVals,
+      additionalMetadata: metadataVals.additionalMetadata.filter(
+        ([k]) => k !== key
+      ),
+    };
+    const accountMetadata = await getAccountMetadata(
+      getConnection(),
+      metadataKeypair.publicKey
+    );
+    assert.deepStrictEqual(accountMetadata, vals);
+
+    // Only the rent freed by the smaller account goes to the destination
+    const metadataAccount = await getConnection().getAccountInfo(
+      metadataKeypair.publicKey
+    );
+    const freedRent =
+      (await getConnection().getMinimumBalanceForRentExemption(
+        metadataBefore!.data.length
+      )) -
+      (await getConnection().getMinimumBalanceForRentExemption(
+        metadataAccount!.data.length
+      ));
+    assert.equal(
+      metadataAccount!.lamports,
+      metadataBefore!.lamports - freedRent
+    );
+    assert.equal(await getConnection().getBalance(destination), freedRent);
+
+    // Update if succeeded
+    metadataVals = vals;
+  }
+
+  it("Remove key with incorrect field authority fails (v2)", async () => {
+    try {
+      await removeKeyWithFieldAuthorityTest(
+        additionalFieldKey,
+        fieldAuthorityKpOne
+      );
+      throw new Error("Should have thrown");
+    } catch (err) {
+      assert(err instanceof SendTransactionError);
+    }
+  });
+
+  it("Remove key with field authority (v2)", async () => {
+    await removeKeyWithFieldAuthorityTest(additionalFieldKey);
+  });
+
+  it("Remove missing key with field authority fails (v2)", async () => {
+    try {
+      await removeKeyWithFieldAuthorityTest(additionalFieldKey);
+      throw new Error("Should have thrown");
+    } catch (err) {
+      assert(err instanceof SendTransactionError);
+    }
+  });
+
   // TODO: Remove field authority with non-update authority fails
 
   // TODO: Test re-adding, re-removing, with and without indempotent
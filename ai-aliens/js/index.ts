@@ -1,6 +1,7 @@
 export const AI_ALIENS_AUTHORITY_PDA_SEED = "ai-aliens-pda";
 export const NFT_MINTED_PDA_SEED = "nft-minted-pda";
 export const NICKNAME_FIELD_KEY = "nickname";
+export const NICKNAME_VALUE_MAX_CHARS = 30;
 
 export function indexToSeed(index: number): Buffer {
   const buffer = Buffer.alloc(2); // 2 bytes
@@ -1,7 +1,17 @@
-use crate::constants::{DUMMY_PUBKEY_STR, METADATA_PROGRAM_ID_STR};
+use crate::constants::{
+    DUMMY_PUBKEY_STR, METADATA_PROGRAM_ID_STR, NICKNAME_FIELD_KEY, NICKNAME_VALUE_MAX_CHARS,
+};
 use crate::errors::AiAliensError;
 
 use anchor_lang::prelude::*;
+use gpl_common::AnchorField;
+use holder_metadata_plugin::{
+    constants::{HOLDER_POLICY_SEED, HOLDER_POLICY_SIGNER_SEED},
+    state::{
+        CharClass, HolderEligibility, HolderFields, HolderPolicyConfig, HolderTokenAccounts,
+        HolderValueRules,
+    },
+};
 use spl_token_metadata_interface::state::TokenMetadata;
 use std::str::FromStr;
 
@@ -50,12 +60,59 @@ pub fn get_token_metadata_max_space(index: u16) -> Result<usize> {
 
     let mut dummy_token_metadata = get_token_metadata_init_vals(index, dummy_pubkey, dummy_pubkey)?;
 
-    // Add nickname additional field with max string length
-    let dummy_max_str = std::iter::repeat('a').take(30).collect();
-    dummy_token_metadata.additional_metadata.push((
-        crate::constants::NICKNAME_FIELD_KEY.to_string(),
-        dummy_max_str,
-    ));
+    // Add nickname additional field with max string length. The policy limits characters, not
+    // bytes, so assume each one takes the most bytes in UTF-8.
+    let dummy_max_str = char::MAX
+        .to_string()
+        .repeat(NICKNAME_VALUE_MAX_CHARS as usize);
+    dummy_token_metadata
+        .additional_metadata
+        .push((NICKNAME_FIELD_KEY.to_string(), dummy_max_str));
 
     return Ok(dummy_token_metadata.tlv_size_of().unwrap());
 }
+
+// Holders may only set the nickname, up to NICKNAME_VALUE_MAX_CHARS characters, and a new
+// holder starts without the previous holder's nickname
+pub fn get_nickname_policy_config(
+    treasury: Pubkey,
+    banned_substrings: Vec<String>,
+) -> HolderPolicyConfig {
+    return HolderPolicyConfig {
+        fields: HolderFields::Only(vec![AnchorField::Key(NICKNAME_FIELD_KEY.to_string())]),
+        values: HolderValueRules {
+            max_chars: Some(NICKNAME_VALUE_MAX_CHARS as u32),
+            allowed_chars: Some(vec![
+                CharClass::Letter,
+                CharClass::Number,
+                CharClass::Space,
+                CharClass::Punctuation,
+            ]),
+            banned_substrings,
+            unique: false,
+        },
+        holders: HolderEligibility {
+            token_accounts: HolderTokenAccounts::default(),
+            min_balance: 1,
+            top_holder_only: false,
+            group: None,
+        },
+        fee: None,
+        treasury,
+        cooldown_seconds: 0,
+        clear_on_transfer: true,
+        moderator: None,
+    };
+}
+
+pub fn get_holder_policy_signer(update_authority: &Pubkey) -> Pubkey {
+    let (holder_policy, _bump) = Pubkey::find_program_address(
+        &[HOLDER_POLICY_SEED.as_bytes(), update_authority.as_ref()],
+        &holder_metadata_plugin::id(),
+    );
+    let (policy_signer, _bump) = Pubkey::find_program_address(
+        &[HOLDER_POLICY_SIGNER_SEED.as_bytes(), holder_policy.as_ref()],
+        &holder_metadata_plugin::id(),
+    );
+    return policy_signer;
+}
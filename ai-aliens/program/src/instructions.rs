@@ -9,6 +9,7 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount},
 };
 use gpl_common::AnchorField;
+use holder_metadata_plugin::program::HolderMetadataPlugin;
 use spl_token_2022::{
     extension::ExtensionType::{
         self, GroupMemberPointer, MetadataPointer, PermanentDelegate, TransferHook,
@@ -90,12 +91,20 @@ pub struct CreateMint<'info> {
     /// CHECK: Account checked in CPI
     #[account(mut)]
     pub field_pda: UncheckedAccount<'info>,
+    // The holder policy, extra account meta list and holder metadata program set up the mint's
+    // transfer hook
+    /// CHECK: Account checked in CPI
+    pub holder_policy: UncheckedAccount<'info>,
+    /// CHECK: Account checked in CPI
+    #[account(mut)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
     // Need to keep on one line to not break rust-analyzer formatting
     /// CHECK: Account checked in constraints
     #[account(
         executable, constraint = metadata_program.key() == get_metadata_program_id()?
     )]
     pub metadata_program: UncheckedAccount<'info>,
+    pub holder_metadata_program: Program<'info, HolderMetadataPlugin>,
     pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
@@ -146,6 +155,25 @@ pub struct UpdateField<'info> {
     pub metadata_program: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct InitHolderPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        // address = ai_aliens_pda.admin
+        // TEMP: https://github.com/coral-xyz/anchor/issues/2912
+        constraint = admin.key() == ai_aliens_pda.admin
+    )]
+    pub admin: Signer<'info>,
+    #[account(seeds = [AI_ALIENS_PDA_SEED.as_bytes()], bump)]
+    pub ai_aliens_pda: Account<'info, AiAliensPda>,
+    /// CHECK: Account checked in CPI
+    #[account(mut)]
+    pub holder_policy: UncheckedAccount<'info>,
+    pub holder_metadata_program: Program<'info, HolderMetadataPlugin>,
+    pub system_program: Program<'info, System>,
+}
+
 // NOTE: We allow anyone to call this to make running it on all mints easier
 #[derive(Accounts)]
 #[instruction(index: u16)]
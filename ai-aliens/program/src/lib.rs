@@ -49,6 +49,13 @@ pub mod ai_aliens {
         return handle_update_field(ctx, field, val);
     }
 
+    pub fn init_holder_policy(
+        ctx: Context<InitHolderPolicy>,
+        banned_substrings: Vec<String>,
+    ) -> Result<()> {
+        return handle_init_holder_policy(ctx, banned_substrings);
+    }
+
     pub fn nullify_mint_authority(ctx: Context<NullifyMintAuthority>, index: u16) -> Result<()> {
         return handle_nullify_mint_authority(ctx, index);
     }
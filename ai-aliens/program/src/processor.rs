@@ -1,12 +1,14 @@
 use crate::constants::{AI_ALIENS_PDA_SEED, NICKNAME_FIELD_KEY};
 use crate::errors::AiAliensError;
-use crate::helpers::{get_token_metadata_init_vals, get_token_metadata_max_space};
+use crate::helpers::{
+    get_holder_policy_signer, get_nickname_policy_config, get_token_metadata_init_vals,
+    get_token_metadata_max_space,
+};
 use crate::instructions::*;
 
 use anchor_lang::{prelude::*, solana_program::program::invoke_signed};
 use anchor_spl::token_interface::{mint_to, MintTo};
 use gpl_common::AnchorField;
-use holder_metadata_plugin::HOLDER_METADATA_PDA_SEED;
 use spl_token_2022::{
     extension::{group_member_pointer, metadata_pointer, transfer_hook},
     instruction::{initialize_mint2, initialize_permanent_delegate, set_authority, AuthorityType},
@@ -97,11 +99,12 @@ fn init_gmp_ext(ctx: &Context<CreateMint>) -> Result<()> {
 }
 
 fn init_th_ext(ctx: &Context<CreateMint>) -> Result<()> {
+    // The holder metadata plugin clears nicknames on transfer
     let ix = transfer_hook::instruction::initialize(
         ctx.accounts.token_program.key,
         &ctx.accounts.mint.key(),
         Some(ctx.accounts.ai_aliens_pda.key()),
-        None,
+        Some(holder_metadata_plugin::id()),
     )?;
     let accounts = [ctx.accounts.mint.to_account_info()];
     let ai_aliens_pda_seeds = [AI_ALIENS_PDA_SEED.as_bytes(), &[ctx.bumps.ai_aliens_pda]];
@@ -171,9 +174,8 @@ fn init_metadata(ctx: &Context<CreateMint>, index: u16) -> Result<()> {
 }
 
 fn add_nickname_as_holder_meta(ctx: &Context<CreateMint>) -> Result<()> {
-    let holder_metadata_pda_seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()];
-    let (holder_metadata_pda, _bump) =
-        Pubkey::find_program_address(&holder_metadata_pda_seeds, &holder_metadata_plugin::id());
+    // Grant our holder policy instead of the global PDA, so nicknames get validated
+    let policy_signer = get_holder_policy_signer(&ctx.accounts.ai_aliens_pda.key());
 
     let ix = field_authority_interface::instructions::add_field_authority(
         ctx.accounts.metadata_program.key,
@@ -181,7 +183,7 @@ fn add_nickname_as_holder_meta(ctx: &Context<CreateMint>) -> Result<()> {
         &ctx.accounts.metadata.key(),
         &ctx.accounts.ai_aliens_pda.key(),
         spl_token_metadata_interface::state::Field::Key(NICKNAME_FIELD_KEY.to_string()),
-        &holder_metadata_pda,
+        &policy_signer,
     );
     let accounts = [
         ctx.accounts.payer.to_account_info(),
@@ -197,6 +199,26 @@ fn add_nickname_as_holder_meta(ctx: &Context<CreateMint>) -> Result<()> {
     Ok(())
 }
 
+fn init_holder_transfer_hook(ctx: &Context<CreateMint>) -> Result<()> {
+    let cpi_program = ctx.accounts.holder_metadata_program.to_account_info();
+    let cpi_accounts = holder_metadata_plugin::cpi::accounts::InitializeHolderTransferHook {
+        payer: ctx.accounts.payer.to_account_info(),
+        update_authority: ctx.accounts.ai_aliens_pda.to_account_info(),
+        holder_policy: ctx.accounts.holder_policy.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        metadata: ctx.accounts.metadata.to_account_info(),
+        extra_account_meta_list: ctx.accounts.extra_account_meta_list.to_account_info(),
+        field_authority_program: ctx.accounts.metadata_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    };
+    let ai_aliens_pda_seeds = &[AI_ALIENS_PDA_SEED.as_bytes(), &[ctx.bumps.ai_aliens_pda]];
+    let signer_seeds = &[&ai_aliens_pda_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    holder_metadata_plugin::cpi::initialize_holder_transfer_hook(cpi_ctx)?;
+
+    Ok(())
+}
+
 fn transfer_lamports_for_nickname(ctx: &Context<CreateMint>, index: u16) -> Result<()> {
     let max_space = get_token_metadata_max_space(index)?;
     let rent_lamports = Rent::get()?.minimum_balance(max_space);
@@ -227,6 +249,7 @@ pub fn handle_create_mint(ctx: Context<CreateMint>, index: u16) -> Result<()> {
     init_mint(&ctx)?;
     init_metadata(&ctx, index)?;
     add_nickname_as_holder_meta(&ctx)?;
+    init_holder_transfer_hook(&ctx)?;
     transfer_lamports_for_nickname(&ctx, index)?;
 
     // Set data of NFT minted PDA
@@ -273,6 +296,27 @@ pub fn handle_update_field(
     Ok(())
 }
 
+pub fn handle_init_holder_policy(
+    ctx: Context<InitHolderPolicy>,
+    banned_substrings: Vec<String>,
+) -> Result<()> {
+    let config = get_nickname_policy_config(ctx.accounts.ai_aliens_pda.treasury, banned_substrings);
+
+    let cpi_program = ctx.accounts.holder_metadata_program.to_account_info();
+    let cpi_accounts = holder_metadata_plugin::cpi::accounts::CreateHolderPolicy {
+        payer: ctx.accounts.payer.to_account_info(),
+        update_authority: ctx.accounts.ai_aliens_pda.to_account_info(),
+        holder_policy: ctx.accounts.holder_policy.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+    };
+    let ai_aliens_pda_seeds = &[AI_ALIENS_PDA_SEED.as_bytes(), &[ctx.bumps.ai_aliens_pda]];
+    let signer_seeds = &[&ai_aliens_pda_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    holder_metadata_plugin::cpi::create_holder_policy(cpi_ctx, config)?;
+
+    Ok(())
+}
+
 pub fn handle_nullify_mint_authority(ctx: Context<NullifyMintAuthority>, index: u16) -> Result<()> {
     // NOTE: Need to use spl-token-2022 crate directly because Anchor has a mismatched version
     // TODO: Match versions and update program carefully
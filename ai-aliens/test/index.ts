@@ -5,14 +5,16 @@ import {
   Keypair,
   PublicKey,
   LAMPORTS_PER_SOL,
-  SendTransactionError,
   SystemProgram,
   sendAndConfirmTransaction,
   Transaction,
 } from "@solana/web3.js";
-import { BN, workspace } from "@coral-xyz/anchor";
+import { AnchorError, BN, workspace } from "@coral-xyz/anchor";
 import {
+  createAssociatedTokenAccountIdempotentInstruction,
+  createTransferCheckedWithTransferHookInstruction,
   getAssociatedTokenAddress,
+  getExtraAccountMetaAddress,
   TOKEN_2022_PROGRAM_ID,
   getMint,
   getMetadataPointerState,
@@ -31,6 +33,10 @@ import {
   fieldToAnchorParam,
 } from "../../common/js";
 import { ATM_PROGRAM_ID } from "../../advanced-token-metadata/js";
+import {
+  getHolderBanPda,
+  getHolderPolicyPda,
+} from "../../holder-metadata-plugin/js";
 import {
   FIELD_AUTHORITY_PDA_SEED,
   fieldToSeedStr,
@@ -39,6 +45,7 @@ import {
   AI_ALIENS_AUTHORITY_PDA_SEED,
   NFT_MINTED_PDA_SEED,
   NICKNAME_FIELD_KEY,
+  NICKNAME_VALUE_MAX_CHARS,
   indexToSeed,
 } from "../js";
 import { AiAliens } from "../../target/types/ai_aliens";
@@ -58,6 +65,12 @@ describe("AI Aliens Program", () => {
     setPayer<AiAliens>(ANCHOR_WALLET_KEYPAIR, workspace.AiAliens).program
       .programId
   );
+  const holderMetadataProgramId = setPayer<HolderMetadataPlugin>(
+    ANCHOR_WALLET_KEYPAIR,
+    workspace.HolderMetadataPlugin
+  ).program.programId;
+  const holderPolicy = getHolderPolicyPda(aiAliensPda, holderMetadataProgramId);
+  const bannedWord = "badword";
 
   function getMetadataVals(index: number): TokenMetadata {
     const mint = mints[index - 1];
@@ -126,6 +139,32 @@ describe("AI Aliens Program", () => {
     assert.equal(aiAliensPdaData.mintPriceLamports, mintPriceLamports);
   });
 
+  it("Init holder policy", async () => {
+    const { program } = setPayer<AiAliens>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.AiAliens
+    );
+
+    await program.methods
+      .initHolderPolicy([bannedWord])
+      .accountsPartial({ holderPolicy })
+      .rpc();
+
+    // Check policy
+    const { program: holderProgram } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+    const policy = await holderProgram.account.holderPolicy.fetch(
+      holderPolicy
+    );
+    assert(policy.updateAuthority.equals(aiAliensPda));
+    assert.equal(policy.config.values.maxChars, NICKNAME_VALUE_MAX_CHARS);
+    assert.deepStrictEqual(policy.config.values.bannedSubstrings, [
+      bannedWord,
+    ]);
+  });
+
   async function createMint(
     index: number,
     payer: Keypair = ANCHOR_WALLET_KEYPAIR
@@ -158,6 +197,11 @@ describe("AI Aliens Program", () => {
         mint: mintKeypair.publicKey,
         metadata: metadataKeypair.publicKey,
         fieldPda,
+        holderPolicy,
+        extraAccountMetaList: getExtraAccountMetaAddress(
+          mintKeypair.publicKey,
+          holderMetadataProgramId
+        ),
         metadataProgram: ATM_PROGRAM_ID,
       })
       .signers([mintKeypair, metadataKeypair])
@@ -200,7 +244,7 @@ describe("AI Aliens Program", () => {
     const transferHook = getTransferHook(mintInfo);
     assert(transferHook);
     assert(transferHook.authority.equals(aiAliensPda));
-    assert(transferHook.programId.equals(PublicKey.default));
+    assert(transferHook.programId.equals(holderMetadataProgramId));
 
     // Check group member pointer
     const groupMemberPointerState = getGroupMemberPointerState(mintInfo);
@@ -343,14 +387,37 @@ describe("AI Aliens Program", () => {
       ATM_PROGRAM_ID
     );
 
+    const holderTokenAccount = await getAssociatedTokenAddress(
+      mint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
     await program.methods
-      .updateHolderField(param, val)
-      .accounts({
+      .updateHolderFieldWithPolicy(param, val)
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
         mint,
         metadata,
+        holderTokenAccount,
+        holderPolicy,
         fieldPda,
+        topHolderTokenAccount: null,
+        treasury,
+        feeMint: null,
+        payerFeeTokenAccount: null,
+        treasuryFeeTokenAccount: null,
+        feeTokenProgram: null,
         fieldAuthorityProgram: ATM_PROGRAM_ID,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
+        ownerBan: getHolderBanPda(
+          holderPolicy,
+          ANCHOR_WALLET_KEYPAIR.publicKey,
+          holderMetadataProgramId
+        ),
       })
       .rpc();
 
@@ -367,18 +434,86 @@ describe("AI Aliens Program", () => {
   }
 
   it("Update nickname", async () => {
-    await updateNickname(1, randomStr(30));
+    await updateNickname(1, randomStr(NICKNAME_VALUE_MAX_CHARS));
   });
 
   it("Update nickname fails with too long nickname", async () => {
-    assert(async () => {
-      try {
-        await updateNickname(1, randomStr(31));
-        throw new Error("Expected error to be thrown");
-      } catch (e) {
-        assert(e instanceof SendTransactionError);
-      }
-    });
+    await assert.rejects(
+      updateNickname(1, randomStr(NICKNAME_VALUE_MAX_CHARS + 1)),
+      (err: AnchorError) => err.error.errorCode.code === "ValueTooLong"
+    );
+  });
+
+  it("Update nickname fails with a line break", async () => {
+    await assert.rejects(
+      updateNickname(1, "Zorg\nthe Great"),
+      (err: AnchorError) => err.error.errorCode.code === "DisallowedCharacter"
+    );
+  });
+
+  it("Update nickname fails with a banned word", async () => {
+    await assert.rejects(
+      updateNickname(1, `Zorg ${bannedWord.toUpperCase()}`),
+      (err: AnchorError) => err.error.errorCode.code === "BannedSubstring"
+    );
+  });
+
+  it("Transfer clears nickname", async () => {
+    const index = 1;
+    const mint = mints[index - 1];
+    const metadata = metadatas[index - 1];
+    const newHolder = Keypair.generate().publicKey;
+
+    const source = await getAssociatedTokenAddress(
+      mint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const destination = await getAssociatedTokenAddress(
+      mint,
+      newHolder,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // Resolves the extra accounts of the holder metadata plugin's transfer hook
+    const transferIx = await createTransferCheckedWithTransferHookInstruction(
+      getConnection(),
+      source,
+      mint,
+      destination,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      BigInt(1),
+      0,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const tx = new Transaction().add(
+      createAssociatedTokenAccountIdempotentInstruction(
+        ANCHOR_WALLET_KEYPAIR.publicKey,
+        destination,
+        newHolder,
+        mint,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      transferIx
+    );
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+    ]);
+
+    // Check metadata
+    const metadataVals = getMetadataVals(index);
+    metadataVals.additionalMetadata.push([NICKNAME_FIELD_KEY, ""]);
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
   });
 
   async function updateUriWithAdmin(index: number, uri: string): Promise<void> {
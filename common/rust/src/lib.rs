@@ -1,4 +1,11 @@
-use {anchor_lang::prelude::*, spl_token_metadata_interface::state::Field, std::str::FromStr};
+use {
+    anchor_lang::prelude::*,
+    field_authority_interface::{
+        field_to_seed, field_to_seed_str, legacy_field_to_seed, FieldSeedVersion,
+    },
+    spl_token_metadata_interface::state::Field,
+    std::str::FromStr,
+};
 
 #[error_code]
 pub enum GplError {
@@ -63,12 +70,21 @@ impl From<Field> for AnchorField {
 }
 
 impl AnchorField {
+    /// Seed string of the field PDA, matching `field_to_seed_str` in the field authority interface
     pub fn seed_str(&self) -> String {
-        match self {
-            AnchorField::Name => "name".to_string(),
-            AnchorField::Symbol => "symbol".to_string(),
-            AnchorField::Uri => "uri".to_string(),
-            AnchorField::Key(key) => format!("key:{}", key),
-        }
+        field_to_seed_str(self.clone().into())
+    }
+
+    /// Seed of the field PDA, hashed when the seed string is too long to be used directly
+    pub fn seed(&self) -> Vec<u8> {
+        let field: Field = self.clone().into();
+        let version = FieldSeedVersion::for_field(&field);
+        field_to_seed(field, version).unwrap()
+    }
+
+    /// Seed of a field PDA created before keys were normalized, matching `legacyFieldToSeed` in
+    /// the JS package
+    pub fn legacy_seed(&self) -> Vec<u8> {
+        legacy_field_to_seed(self.clone().into())
     }
 }
@@ -1 +1,4 @@
 pub const FIELD_AUTHORITY_PDA_SEED: &str = "field-authority-pda";
+
+/// Additional metadata keys starting with this prefix can only be written by the metadata program
+pub const RESERVED_KEY_PREFIX: &str = "sys:";
@@ -1,7 +1,48 @@
-use spl_token_metadata_interface::state::Field;
+use {
+    crate::{FIELD_AUTHORITY_PDA_SEED, RESERVED_KEY_PREFIX},
+    solana_program::{
+        hash::hash,
+        pubkey::{Pubkey, MAX_SEED_LEN},
+    },
+    spl_token_metadata_interface::state::Field,
+    unicode_normalization::UnicodeNormalization,
+};
 
-/// Convert a field to a string to use as a seed in the field PDA
+/// Normalize an additional metadata key: trimmed of Unicode whitespace, lowercase and Unicode
+/// NFC. Characters are lowercased one by one, without context-dependent rules like the final
+/// sigma, so clients can reproduce the result byte for byte.
+pub fn normalize_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .nfc()
+        .collect()
+}
+
+/// Normalize a field. Only `Field::Key` is affected.
+pub fn normalize_field(field: Field) -> Field {
+    match field {
+        Field::Key(key) => Field::Key(normalize_key(&key)),
+        field => field,
+    }
+}
+
+/// Check if a field is an additional metadata key in the reserved namespace
+pub fn is_reserved_field(field: &Field) -> bool {
+    match field {
+        Field::Key(key) => normalize_key(key).starts_with(RESERVED_KEY_PREFIX),
+        _ => false,
+    }
+}
+
+/// Convert a field to a string to use as a seed in the field PDA. Keys are normalized first.
 pub fn field_to_seed_str(field: Field) -> String {
+    legacy_field_to_seed_str(normalize_field(field))
+}
+
+/// Convert a field to a seed string without normalizing the key, like field PDAs created before
+/// keys were normalized
+pub fn legacy_field_to_seed_str(field: Field) -> String {
     match field {
         Field::Name => "name".to_string(),
         Field::Symbol => "symbol".to_string(),
@@ -9,3 +50,112 @@ pub fn field_to_seed_str(field: Field) -> String {
         Field::Key(key) => format!("key:{}", key),
     }
 }
+
+/// Version of the field seed used to derive the field PDA
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldSeedVersion {
+    /// The seed string itself. Only usable when it fits in a single seed.
+    Raw,
+    /// The sha256 hash of the seed string
+    Hashed,
+}
+
+impl FieldSeedVersion {
+    /// All versions, in the order they're tried when resolving a field PDA
+    pub const ALL: [FieldSeedVersion; 2] = [FieldSeedVersion::Raw, FieldSeedVersion::Hashed];
+
+    /// Version used for new field PDAs. Raw seeds are kept whenever they fit, so existing PDAs
+    /// still resolve, and longer keys fall back to hashed seeds.
+    pub fn for_field(field: &Field) -> Self {
+        Self::for_seed_str(&field_to_seed_str(field.clone()))
+    }
+
+    fn for_seed_str(seed_str: &str) -> Self {
+        if seed_str.len() <= MAX_SEED_LEN {
+            FieldSeedVersion::Raw
+        } else {
+            FieldSeedVersion::Hashed
+        }
+    }
+
+    /// Convert a seed string to a seed. Returns `None` if a raw seed doesn't fit.
+    fn seed(self, seed_str: &str) -> Option<Vec<u8>> {
+        match self {
+            FieldSeedVersion::Raw if seed_str.len() <= MAX_SEED_LEN => {
+                Some(seed_str.as_bytes().to_vec())
+            }
+            FieldSeedVersion::Raw => None,
+            FieldSeedVersion::Hashed => Some(hash(seed_str.as_bytes()).to_bytes().to_vec()),
+        }
+    }
+}
+
+/// Convert a field to the seed used in the field PDA. Returns `None` if a raw seed doesn't fit.
+pub fn field_to_seed(field: Field, version: FieldSeedVersion) -> Option<Vec<u8>> {
+    version.seed(&field_to_seed_str(field))
+}
+
+/// Find the field PDA of a field for a given seed version
+pub fn find_field_pda(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    field: Field,
+    version: FieldSeedVersion,
+) -> Option<(Pubkey, u8)> {
+    let field_seed = field_to_seed(field, version)?;
+    let field_pda_seeds = [
+        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
+        &field_seed,
+        metadata.as_ref(),
+    ];
+    Some(Pubkey::find_program_address(&field_pda_seeds, program_id))
+}
+
+/// Convert a field to the seed of a field PDA created before keys were normalized, hashed when
+/// the seed string is too long to be used directly
+pub fn legacy_field_to_seed(field: Field) -> Vec<u8> {
+    let seed_str = legacy_field_to_seed_str(field);
+    FieldSeedVersion::for_seed_str(&seed_str)
+        .seed(&seed_str)
+        .unwrap()
+}
+
+/// Find the field PDA of a field created before keys were normalized, derived from the key as
+/// written
+pub fn find_legacy_field_pda(program_id: &Pubkey, metadata: &Pubkey, field: Field) -> (Pubkey, u8) {
+    let field_seed = legacy_field_to_seed(field);
+    let field_pda_seeds = [
+        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
+        &field_seed,
+        metadata.as_ref(),
+    ];
+    Pubkey::find_program_address(&field_pda_seeds, program_id)
+}
+
+/// Resolve the seed of an existing field PDA, given the field as written. Field PDAs are derived
+/// from the normalized key, or from the key as written if they were created before keys were
+/// normalized, with any seed version. Returns the field seed and bump, or `None` if the PDA
+/// matches none of them.
+pub fn resolve_field_pda_seed(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    field: Field,
+    field_pda: &Pubkey,
+) -> Option<(Vec<u8>, u8)> {
+    let seed_strs = [
+        field_to_seed_str(field.clone()),
+        legacy_field_to_seed_str(field),
+    ];
+    seed_strs.iter().find_map(|seed_str| {
+        FieldSeedVersion::ALL.iter().find_map(|version| {
+            let field_seed = version.seed(seed_str)?;
+            let field_pda_seeds = [
+                FIELD_AUTHORITY_PDA_SEED.as_bytes(),
+                &field_seed,
+                metadata.as_ref(),
+            ];
+            let (pda, bump) = Pubkey::find_program_address(&field_pda_seeds, program_id);
+            (pda == *field_pda).then_some((field_seed, bump))
+        })
+    })
+}
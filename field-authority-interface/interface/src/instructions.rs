@@ -1,11 +1,11 @@
 use {
     crate::{
-        field_to_seed_str,
+        find_field_pda,
         instructions_v2::{
             AddFieldAuthorityV2, InitializeFieldAuthorities, RemoveFieldAuthorityV2,
-            UpdateFieldWithFieldAuthorityV2,
+            RemoveKeyWithFieldAuthorityV2, UpdateFieldWithFieldAuthorityV2,
         },
-        FIELD_AUTHORITY_PDA_SEED,
+        FieldSeedVersion,
     },
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
@@ -46,6 +46,7 @@ pub enum FieldAuthorityInstruction {
     UpdateFieldWithFieldAuthorityV2(UpdateFieldWithFieldAuthorityV2),
     RemoveFieldAuthority(RemoveFieldAuthority),
     RemoveFieldAuthorityV2(RemoveFieldAuthorityV2),
+    RemoveKeyWithFieldAuthorityV2(RemoveKeyWithFieldAuthorityV2),
 }
 
 impl FieldAuthorityInstruction {
@@ -83,6 +84,10 @@ impl FieldAuthorityInstruction {
                 let data = RemoveFieldAuthorityV2::try_from_slice(rest)?;
                 Self::RemoveFieldAuthorityV2(data)
             }
+            RemoveKeyWithFieldAuthorityV2::SPL_DISCRIMINATOR_SLICE => {
+                let data = RemoveKeyWithFieldAuthorityV2::try_from_slice(rest)?;
+                Self::RemoveKeyWithFieldAuthorityV2(data)
+            }
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
@@ -118,6 +123,10 @@ impl FieldAuthorityInstruction {
                 buf.extend_from_slice(RemoveFieldAuthorityV2::SPL_DISCRIMINATOR_SLICE);
                 buf.append(&mut borsh::to_vec(data).unwrap());
             }
+            Self::RemoveKeyWithFieldAuthorityV2(data) => {
+                buf.extend_from_slice(RemoveKeyWithFieldAuthorityV2::SPL_DISCRIMINATOR_SLICE);
+                buf.append(&mut borsh::to_vec(data).unwrap());
+            }
         };
         buf
     }
@@ -133,13 +142,8 @@ pub fn add_field_authority(
     field_authority: &Pubkey,
 ) -> Instruction {
     // Calculate PDA
-    let field_seed_str = field_to_seed_str(field.clone());
-    let field_pda_seeds = [
-        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
-        metadata.as_ref(),
-    ];
-    let (field_pda, _bump) = Pubkey::find_program_address(&field_pda_seeds, program_id);
+    let version = FieldSeedVersion::for_field(&field);
+    let (field_pda, _bump) = find_field_pda(program_id, metadata, field.clone(), version).unwrap();
 
     let data = FieldAuthorityInstruction::AddFieldAuthority(AddFieldAuthority {
         field,
@@ -168,14 +172,29 @@ pub fn update_field_with_field_authority(
     value: String,
 ) -> Instruction {
     // Calculate PDA
-    let field_seed_str = field_to_seed_str(field.clone());
-    let field_pda_seeds = [
-        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
-        metadata.as_ref(),
-    ];
-    let (field_pda, _bump) = Pubkey::find_program_address(&field_pda_seeds, program_id);
+    let version = FieldSeedVersion::for_field(&field);
+    let (field_pda, _bump) = find_field_pda(program_id, metadata, field.clone(), version).unwrap();
+
+    update_field_with_field_authority_for_pda(
+        program_id,
+        metadata,
+        field_authority,
+        &field_pda,
+        field,
+        value,
+    )
+}
 
+/// Creates `UpdateFieldWithFieldAuthority` instruction for a given field PDA, e.g. one created
+/// before keys were normalized, found with `find_legacy_field_pda`
+pub fn update_field_with_field_authority_for_pda(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    field_authority: &Pubkey,
+    field_pda: &Pubkey,
+    field: Field,
+    value: String,
+) -> Instruction {
     let data =
         FieldAuthorityInstruction::UpdateFieldWithFieldAuthority(UpdateFieldWithFieldAuthority {
             field,
@@ -187,7 +206,7 @@ pub fn update_field_with_field_authority(
         accounts: vec![
             AccountMeta::new(*metadata, false),
             AccountMeta::new_readonly(*field_authority, true),
-            AccountMeta::new_readonly(field_pda, false),
+            AccountMeta::new_readonly(*field_pda, false),
         ],
         data: data.pack(),
     }
@@ -201,22 +220,30 @@ pub fn remove_field_authority(
     field: Field,
 ) -> Instruction {
     // Calculate PDA
-    let field_seed_str = field_to_seed_str(field.clone());
-    let field_pda_seeds = [
-        FIELD_AUTHORITY_PDA_SEED.as_bytes(),
-        field_seed_str.as_bytes(),
-        metadata.as_ref(),
-    ];
-    let (field_pda, _bump) = Pubkey::find_program_address(&field_pda_seeds, program_id);
+    let version = FieldSeedVersion::for_field(&field);
+    let (field_pda, _bump) = find_field_pda(program_id, metadata, field.clone(), version).unwrap();
+
+    remove_field_authority_for_pda(program_id, metadata, update_authority, &field_pda, field)
+}
 
+/// Creates `RemoveFieldAuthority` instruction for a given field PDA, e.g. one created before keys
+/// were normalized, found with `find_legacy_field_pda`
+pub fn remove_field_authority_for_pda(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    update_authority: &Pubkey,
+    field_pda: &Pubkey,
+    field: Field,
+) -> Instruction {
     let data = FieldAuthorityInstruction::RemoveFieldAuthority(RemoveFieldAuthority { field });
 
     Instruction {
         program_id: *program_id,
         accounts: vec![
             AccountMeta::new(*metadata, false),
-            AccountMeta::new_readonly(*update_authority, true),
-            AccountMeta::new(field_pda, false),
+            // Receives the lamports of the closed field PDA
+            AccountMeta::new(*update_authority, true),
+            AccountMeta::new(*field_pda, false),
         ],
         data: data.pack(),
     }
@@ -36,6 +36,13 @@ pub struct RemoveFieldAuthorityV2 {
     pub field_authority: FieldAuthority,
 }
 
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, SplDiscriminate)]
+#[discriminator_hash_input("field_authority_interface:remove_key_with_field_authority_v2")]
+pub struct RemoveKeyWithFieldAuthorityV2 {
+    pub idempotent: bool,
+    pub key: String,
+}
+
 /// Creates `InitializeFieldAuthorities` instruction
 pub fn initialize_field_authorities(
     program_id: &Pubkey,
@@ -73,7 +80,7 @@ pub fn add_field_authority_v2(
     Instruction {
         program_id: *program_id,
         accounts: vec![
-            AccountMeta::new_readonly(*metadata, false),
+            AccountMeta::new(*metadata, false),
             AccountMeta::new_readonly(*update_authority, true),
         ],
         data: data.pack(),
@@ -119,9 +126,37 @@ pub fn remove_field_authority_v2(
     Instruction {
         program_id: *program_id,
         accounts: vec![
-            AccountMeta::new_readonly(*metadata, false),
+            AccountMeta::new(*metadata, false),
             AccountMeta::new_readonly(*update_authority, true),
         ],
         data: data.pack(),
     }
 }
+
+/// Creates `RemoveKeyWithFieldAuthorityV2` instruction
+///
+/// The rent freed by shrinking the metadata account is sent to `destination`
+pub fn remove_key_with_field_authority_v2(
+    program_id: &Pubkey,
+    metadata: &Pubkey,
+    field_authority: &Pubkey,
+    destination: &Pubkey,
+    key: String,
+    idempotent: bool,
+) -> Instruction {
+    let data =
+        FieldAuthorityInstruction::RemoveKeyWithFieldAuthorityV2(RemoveKeyWithFieldAuthorityV2 {
+            idempotent,
+            key,
+        });
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*metadata, false),
+            AccountMeta::new_readonly(*field_authority, true),
+            AccountMeta::new(*destination, false),
+        ],
+        data: data.pack(),
+    }
+}
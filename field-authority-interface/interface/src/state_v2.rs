@@ -1,4 +1,5 @@
 use {
+    crate::normalize_field,
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         borsh1::{get_instance_packed_len, try_from_slice_unchecked},
@@ -19,6 +20,14 @@ pub struct FieldAuthority {
     pub field: Field,
     pub authority: Pubkey,
 }
+impl FieldAuthority {
+    /// Checks if both are the same pair. Keys are compared by their normalized form, so pairs
+    /// stored before keys were normalized still match.
+    fn matches(&self, other: &FieldAuthority) -> bool {
+        self.authority == other.authority
+            && normalize_field(self.field.clone()) == normalize_field(other.field.clone())
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, SplDiscriminate)]
 #[discriminator_hash_input("field_authorities")]
@@ -49,7 +58,7 @@ impl FieldAuthorities {
     /// Adds a field authority. Returns true if the field authority was added (and wasn't found).
     pub fn add_field_authority(&mut self, field_authority: FieldAuthority) -> bool {
         for fa in &self.authorities {
-            if fa.field == field_authority.field && fa.authority == field_authority.authority {
+            if fa.matches(&field_authority) {
                 return false;
             }
         }
@@ -60,7 +69,7 @@ impl FieldAuthorities {
     /// Checks if a field authority pair exists. Returns true if found.
     pub fn contains_field_authority(&self, field_authority: FieldAuthority) -> bool {
         for fa in &self.authorities {
-            if fa.field == field_authority.field && fa.authority == field_authority.authority {
+            if fa.matches(&field_authority) {
                 return true;
             }
         }
@@ -71,8 +80,7 @@ impl FieldAuthorities {
     pub fn remove_field_authority(&mut self, field_authority: FieldAuthority) -> bool {
         let mut found = false;
         self.authorities.retain(|fa| {
-            let should_retain =
-                !(fa.field == field_authority.field && fa.authority == field_authority.authority);
+            let should_retain = !fa.matches(&field_authority);
             if !should_retain {
                 found = true;
             }
@@ -167,3 +167,39 @@ export function createRemoveFieldAuthorityV2Ix(
     ),
   });
 }
+
+export interface RemoveKeyWithFieldAuthorityV2Args {
+  programId: PublicKey;
+  metadata: PublicKey;
+  fieldAuthority: PublicKey;
+  destination: PublicKey;
+  key: string;
+  idempotent: boolean;
+}
+
+export function createRemoveKeyWithFieldAuthorityV2Ix(
+  args: RemoveKeyWithFieldAuthorityV2Args
+): TransactionInstruction {
+  const { programId, metadata, fieldAuthority, destination, key, idempotent } =
+    args;
+
+  return new TransactionInstruction({
+    programId,
+    keys: [
+      { isSigner: false, isWritable: true, pubkey: metadata },
+      { isSigner: true, isWritable: false, pubkey: fieldAuthority },
+      { isSigner: false, isWritable: true, pubkey: destination },
+    ],
+    data: Buffer.from(
+      getInstructionEncoder(
+        splDiscriminate(
+          "field_authority_interface:remove_key_with_field_authority_v2"
+        ),
+        getStructEncoder([
+          ["idempotent", getBooleanEncoder()],
+          ["key", getStringEncoder()],
+        ])
+      ).encode({ idempotent, key })
+    ),
+  });
+}
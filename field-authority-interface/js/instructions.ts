@@ -21,7 +21,7 @@ import {
 } from "@solana/codecs";
 import type { Encoder } from "@solana/codecs";
 
-import { FIELD_AUTHORITY_PDA_SEED, fieldToSeedStr } from "./state";
+import { FIELD_AUTHORITY_PDA_SEED, fieldToSeed } from "./state";
 
 // These functions are from: https://github.com/solana-labs/solana-program-library/blob/8c8e7de68b96f8853fdc555ce0af3cfdc717bf55/token-metadata/js/src/instruction.ts
 
@@ -55,7 +55,7 @@ export function createAddFieldAuthorityIx(
   const [fieldPda] = PublicKey.findProgramAddressSync(
     [
       Buffer.from(FIELD_AUTHORITY_PDA_SEED),
-      Buffer.from(fieldToSeedStr(field)),
+      fieldToSeed(field),
       metadata.toBuffer(),
     ],
     programId
@@ -113,16 +113,21 @@ export function createUpdateFieldWithFieldAuthorityIx(
   fieldAuthority: PublicKey,
   field: Field | string,
   value: string,
-  programId: PublicKey
+  programId: PublicKey,
+  // Field PDAs created before keys were normalized are derived with
+  // legacyFieldToSeed instead
+  fieldPda?: PublicKey
 ): TransactionInstruction {
-  const [pda] = PublicKey.findProgramAddressSync(
-    [
-      Buffer.from(FIELD_AUTHORITY_PDA_SEED),
-      Buffer.from(fieldToSeedStr(field)),
-      metadata.toBuffer(),
-    ],
-    programId
-  );
+  const pda =
+    fieldPda ??
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(FIELD_AUTHORITY_PDA_SEED),
+        fieldToSeed(field),
+        metadata.toBuffer(),
+      ],
+      programId
+    )[0];
 
   return new TransactionInstruction({
     programId,
@@ -160,16 +165,21 @@ export function createRemoveFieldAuthorityIx(
   metadata: PublicKey,
   updateAuthority: PublicKey,
   field: Field | string,
-  programId: PublicKey
+  programId: PublicKey,
+  // Field PDAs created before keys were normalized are derived with
+  // legacyFieldToSeed instead
+  fieldPda?: PublicKey
 ): TransactionInstruction {
-  const [pda] = PublicKey.findProgramAddressSync(
-    [
-      Buffer.from(FIELD_AUTHORITY_PDA_SEED),
-      Buffer.from(fieldToSeedStr(field)),
-      metadata.toBuffer(),
-    ],
-    programId
-  );
+  const pda =
+    fieldPda ??
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(FIELD_AUTHORITY_PDA_SEED),
+        fieldToSeed(field),
+        metadata.toBuffer(),
+      ],
+      programId
+    )[0];
 
   return new TransactionInstruction({
     programId,
@@ -1,8 +1,37 @@
+import { createHash } from "crypto";
 import { Field } from "@solana/spl-token-metadata";
 
 export const FIELD_AUTHORITY_PDA_SEED = "field-authority-pda";
+export const RESERVED_KEY_PREFIX = "sys:";
+export const MAX_SEED_LEN = 32;
+
+// Unicode White_Space, as trimmed by the program
+const WHITESPACE =
+  "[\\t\\n\\v\\f\\r \\u0085\\u00a0\\u1680\\u2000-\\u200a\\u2028\\u2029\\u202f\\u205f\\u3000]";
+const TRIM = new RegExp(`^${WHITESPACE}+|${WHITESPACE}+$`, "g");
+
+// Characters are lowercased one by one, like the program, so context-dependent
+// rules like the final sigma don't apply
+export function normalizeKey(key: string): string {
+  return Array.from(key.replace(TRIM, ""), (c) => c.toLowerCase())
+    .join("")
+    .normalize("NFC");
+}
 
 export function fieldToSeedStr(field: Field | string): string {
+  switch (field) {
+    case Field.Name:
+    case Field.Symbol:
+    case Field.Uri:
+      return legacyFieldToSeedStr(field);
+    default:
+      return legacyFieldToSeedStr(normalizeKey(field));
+  }
+}
+
+// Field PDAs created before keys were normalized are derived from the key as
+// written
+export function legacyFieldToSeedStr(field: Field | string): string {
   switch (field) {
     case Field.Name:
       return "name";
@@ -14,3 +43,20 @@ export function fieldToSeedStr(field: Field | string): string {
       return `key:${field}`;
   }
 }
+
+// Raw seeds are used whenever they fit so existing PDAs still resolve, longer ones are hashed
+export function fieldToSeed(field: Field | string): Buffer {
+  return seedStrToSeed(fieldToSeedStr(field));
+}
+
+export function legacyFieldToSeed(field: Field | string): Buffer {
+  return seedStrToSeed(legacyFieldToSeedStr(field));
+}
+
+function seedStrToSeed(seedStr: string): Buffer {
+  const seed = Buffer.from(seedStr);
+  if (seed.length <= MAX_SEED_LEN) {
+    return seed;
+  }
+  return createHash("sha256").update(seed).digest();
+}
@@ -0,0 +1,15 @@
+import assert from "assert";
+
+import { describe, it } from "vitest";
+
+import { normalizeKey } from "../js";
+import vectors from "./normalize-key-vectors.json";
+
+describe("normalizeKey", () => {
+  // the same vectors are checked against the program
+  it("matches the shared vectors", () => {
+    for (const { input, expected } of vectors) {
+      assert.equal(normalizeKey(input), expected);
+    }
+  });
+});
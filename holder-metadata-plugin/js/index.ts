@@ -1 +1,142 @@
+import { createHash } from "crypto";
+import { Connection, PublicKey } from "@solana/web3.js";
+import { TlvState } from "@solana/spl-type-length-value";
+import {
+  Field,
+  TOKEN_METADATA_DISCRIMINATOR,
+  unpack,
+} from "@solana/spl-token-metadata";
+
+import { fieldToSeed, normalizeKey } from "../../field-authority-interface/js";
+
 export const HOLDER_METADATA_PDA_SEED = "holder-metadata-pda";
+export const HOLDER_POLICY_SEED = "holder-policy";
+export const HOLDER_POLICY_SIGNER_SEED = "holder-policy-signer";
+export const HOLDER_FIELD_STATE_SEED = "holder-field-state";
+export const HOLDER_SESSION_SEED = "holder-session";
+export const HOLDER_BAN_SEED = "holder-ban";
+export const HOLDER_VALUE_CLAIM_SEED = "holder-value-claim";
+
+export function getHolderPolicyPda(
+  updateAuthority: PublicKey,
+  programId: PublicKey
+): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from(HOLDER_POLICY_SEED), updateAuthority.toBuffer()],
+    programId
+  );
+  return pda;
+}
+
+export function getHolderPolicySignerPda(
+  holderPolicy: PublicKey,
+  programId: PublicKey
+): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from(HOLDER_POLICY_SIGNER_SEED), holderPolicy.toBuffer()],
+    programId
+  );
+  return pda;
+}
+
+export function getHolderSessionPda(
+  holder: PublicKey,
+  sessionKey: PublicKey,
+  programId: PublicKey
+): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from(HOLDER_SESSION_SEED),
+      holder.toBuffer(),
+      sessionKey.toBuffer(),
+    ],
+    programId
+  );
+  return pda;
+}
+
+export function getHolderBanPda(
+  holderPolicy: PublicKey,
+  target: PublicKey,
+  programId: PublicKey
+): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from(HOLDER_BAN_SEED), holderPolicy.toBuffer(), target.toBuffer()],
+    programId
+  );
+  return pda;
+}
+
+export function hashHolderValue(value: string): Buffer {
+  return createHash("sha256").update(normalizeKey(value)).digest();
+}
+
+export function getHolderValueClaimPda(
+  holderPolicy: PublicKey,
+  field: Field | string,
+  value: string,
+  programId: PublicKey
+): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from(HOLDER_VALUE_CLAIM_SEED),
+      holderPolicy.toBuffer(),
+      fieldToSeed(field),
+      hashHolderValue(value),
+    ],
+    programId
+  );
+  return pda;
+}
+
+// Resolves a claimed value to its mint, or null if no mint holds it anymore
+export async function getHolderValueMint(
+  connection: Connection,
+  holderPolicy: PublicKey,
+  field: Field | string,
+  value: string,
+  programId: PublicKey
+): Promise<PublicKey | null> {
+  const claim = await connection.getAccountInfo(
+    getHolderValueClaimPda(holderPolicy, field, value, programId)
+  );
+  if (!claim) {
+    return null;
+  }
+  // Skip the discriminator and holder policy
+  const mint = new PublicKey(claim.data.subarray(40, 72));
+  const metadata = new PublicKey(claim.data.subarray(72, 104));
+
+  // Claims outlive values reset by a transfer or a moderator
+  const metadataInfo = await connection.getAccountInfo(metadata);
+  if (!metadataInfo) {
+    return null;
+  }
+  const buffer = new TlvState(metadataInfo.data, 8, 4).firstBytes(
+    TOKEN_METADATA_DISCRIMINATOR
+  );
+  if (!buffer) {
+    return null;
+  }
+  const tokenMetadata = unpack(buffer);
+  let current: string | undefined;
+  switch (field) {
+    case Field.Name:
+      current = tokenMetadata.name;
+      break;
+    case Field.Symbol:
+      current = tokenMetadata.symbol;
+      break;
+    case Field.Uri:
+      current = tokenMetadata.uri;
+      break;
+    default:
+      current = tokenMetadata.additionalMetadata.find(
+        ([key]) => normalizeKey(key) === normalizeKey(field)
+      )?.[1];
+  }
+  if (current === undefined || normalizeKey(current) !== normalizeKey(value)) {
+    return null;
+  }
+  return mint;
+}
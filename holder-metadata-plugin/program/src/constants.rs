@@ -1 +1,15 @@
+use anchor_lang::prelude::*;
+
+// The only field authority program trusted with the policy and holder metadata signers
+pub const ATM_PROGRAM_ID: Pubkey = pubkey!("2GkHVZ2y5wP4nw4uA2GWFnc7jphfjKbbcEKwqMCV42a6");
+
 pub const HOLDER_METADATA_PDA_SEED: &str = "holder-metadata-pda";
+pub const HOLDER_POLICY_SEED: &str = "holder-policy";
+pub const HOLDER_POLICY_SIGNER_SEED: &str = "holder-policy-signer";
+pub const HOLDER_FIELD_STATE_SEED: &str = "holder-field-state";
+pub const HOLDER_SESSION_SEED: &str = "holder-session";
+pub const HOLDER_BAN_SEED: &str = "holder-ban";
+pub const HOLDER_VALUE_CLAIM_SEED: &str = "holder-value-claim";
+pub const EXTRA_ACCOUNT_METAS_SEED: &str = "extra-account-metas";
+
+pub const MAX_HOLDER_SESSION_SECONDS: i64 = 7 * 24 * 60 * 60; // 1 week
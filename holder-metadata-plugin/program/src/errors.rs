@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum HolderMetadataPluginError {
+    #[msg("Metadata doesn't belong to the mint.")]
+    MetadataMintMismatch,
+    #[msg("Mint's metadata pointer doesn't point to the metadata.")]
+    MetadataPointerMismatch,
+    #[msg("Metadata update authority doesn't own the holder policy.")]
+    PolicyUpdateAuthorityMismatch,
+    #[msg("Field isn't editable by holders.")]
+    FieldNotAllowed,
+    #[msg("Value is too long.")]
+    ValueTooLong,
+    #[msg("Field was edited too recently.")]
+    CooldownActive,
+    #[msg("Value contains a character the policy doesn't allow.")]
+    DisallowedCharacter,
+    #[msg("Value contains a banned word.")]
+    BannedSubstring,
+    #[msg("Token fee accounts are missing.")]
+    FeeAccountsMissing,
+    #[msg("Fee mint doesn't match the policy.")]
+    FeeMintMismatch,
+    #[msg("Fee token account doesn't belong to the treasury.")]
+    FeeTreasuryMismatch,
+    #[msg("Mint's transfer hook doesn't point to this program.")]
+    TransferHookMismatch,
+    #[msg("Token account isn't being transferred.")]
+    NotTransferring,
+    #[msg("Signer doesn't hold the token.")]
+    NotTokenHolder,
+    #[msg("Token account is frozen.")]
+    TokenAccountFrozen,
+    #[msg("Token account isn't the holder's associated token account.")]
+    TokenAccountNotAssociated,
+    #[msg("Token balance is below the policy's minimum.")]
+    BalanceTooLow,
+    #[msg("Top holder's token account is missing or doesn't match.")]
+    TopHolderAccountMismatch,
+    #[msg("Balance isn't larger than the top holder's.")]
+    NotTopHolder,
+    #[msg("Session must expire in the future and within the maximum duration.")]
+    InvalidSessionExpiry,
+    #[msg("Neither the holder nor a session key of the holder signed.")]
+    HolderNotSigner,
+    #[msg("Session has expired.")]
+    SessionExpired,
+    #[msg("Metadata stores field authorities in field PDAs, so the field PDA is required.")]
+    FieldPdaMissing,
+    #[msg("Mint isn't a member of the policy's token group.")]
+    NotGroupMember,
+    #[msg("Signer isn't the policy's moderator or update authority.")]
+    NotModerator,
+    #[msg("Mint or holder is banned from editing.")]
+    HolderBanned,
+    #[msg("Value claim accounts are missing or don't match.")]
+    ValueClaimMismatch,
+    #[msg("Value is already claimed by another mint.")]
+    ValueTaken,
+    #[msg("Mint doesn't store its metadata in itself.")]
+    EmbeddedMetadataMissing,
+    #[msg("Metadata isn't the metadata of the policy's token group.")]
+    NotGroupMetadata,
+    #[msg("Cooldown can't be negative.")]
+    InvalidCooldown,
+    #[msg("Clearing fields on transfer needs an explicit list of fields.")]
+    ClearOnTransferNeedsListedFields,
+}
@@ -0,0 +1,259 @@
+use crate::errors::HolderMetadataPluginError;
+use crate::state::{HolderFields, HolderPolicy, HolderSession};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{borsh1::try_from_slice_unchecked, hash::hash},
+};
+use field_authority_interface::{
+    normalize_key,
+    state::{FieldAuthorities, FieldAuthority, FieldAuthorityAccount},
+};
+use gpl_common::AnchorField;
+use spl_token_2022::{
+    extension::{
+        group_member_pointer::GroupMemberPointer,
+        metadata_pointer::MetadataPointer,
+        transfer_hook::{self, TransferHookAccount},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::{Account as TokenAccountState, Mint},
+};
+use spl_token_group_interface::state::TokenGroupMember;
+use spl_token_metadata_interface::state::TokenMetadata;
+use spl_type_length_value::state::{TlvState, TlvStateBorrowed};
+
+/// Returns the token metadata stored in the account
+pub fn get_token_metadata(metadata: &AccountInfo) -> Result<TokenMetadata> {
+    let buffer = metadata.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    Ok(state.get_first_variable_len_value::<TokenMetadata>()?)
+}
+
+/// Returns the current value of a field, empty when the key isn't set
+pub fn get_field_value(token_metadata: &TokenMetadata, field: &AnchorField) -> String {
+    match field {
+        AnchorField::Name => token_metadata.name.clone(),
+        AnchorField::Symbol => token_metadata.symbol.clone(),
+        AnchorField::Uri => token_metadata.uri.clone(),
+        AnchorField::Key(key) => {
+            // Keys written before normalization are matched by their normalized form
+            let key = normalize_key(key);
+            token_metadata
+                .additional_metadata
+                .iter()
+                .find(|(k, _)| normalize_key(k) == key)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Hash of a value normalized like keys, used as the seed of its claim
+pub fn hash_holder_value(val: &str) -> [u8; 32] {
+    hash(normalize_key(val).as_bytes()).to_bytes()
+}
+
+/// Checks the metadata belongs to the mint, so holding a token of one collection doesn't
+/// authorize writes to the metadata of another. Returns the metadata.
+pub fn check_metadata_mint(metadata: &AccountInfo, mint: &AccountInfo) -> Result<TokenMetadata> {
+    let token_metadata = get_token_metadata(metadata)?;
+    if token_metadata.mint != *mint.key {
+        return err!(HolderMetadataPluginError::MetadataMintMismatch);
+    }
+
+    // Token-2022 mints can also point to their metadata
+    if *mint.owner == spl_token_2022::id() {
+        let mint_data = mint.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        if let Ok(metadata_pointer) = mint_state.get_extension::<MetadataPointer>() {
+            let metadata_address: Option<Pubkey> = metadata_pointer.metadata_address.into();
+            if metadata_address.is_some_and(|address| address != *metadata.key) {
+                return err!(HolderMetadataPluginError::MetadataPointerMismatch);
+            }
+        }
+    }
+
+    Ok(token_metadata)
+}
+
+/// Checks the metadata is under the policy's update authority, since policies only apply to
+/// metadata of their own update authority
+pub fn check_policy_update_authority(
+    token_metadata: &TokenMetadata,
+    holder_policy: &HolderPolicy,
+) -> Result<()> {
+    let update_authority: Option<Pubkey> = token_metadata.update_authority.into();
+    if update_authority != Some(holder_policy.update_authority) {
+        return err!(HolderMetadataPluginError::PolicyUpdateAuthorityMismatch);
+    }
+    Ok(())
+}
+
+/// Checks the Token-2022 mint stores its metadata in itself and returns it
+pub fn get_embedded_metadata(mint: &AccountInfo) -> Result<TokenMetadata> {
+    if *mint.owner != spl_token_2022::id() {
+        return err!(HolderMetadataPluginError::EmbeddedMetadataMissing);
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let metadata_address: Option<Pubkey> = mint_state
+        .get_extension::<MetadataPointer>()
+        .ok()
+        .and_then(|pointer| pointer.metadata_address.into());
+    if metadata_address != Some(*mint.key) {
+        return err!(HolderMetadataPluginError::EmbeddedMetadataMissing);
+    }
+    mint_state
+        .get_variable_len_extension::<TokenMetadata>()
+        .or(err!(HolderMetadataPluginError::EmbeddedMetadataMissing))
+}
+
+/// Checks the mint is a member of the group. Only member data stored in the mint itself is
+/// trusted, since Token-2022 checks it against the group when initializing it.
+pub fn check_group_member(mint: &AccountInfo, group: &Pubkey) -> Result<()> {
+    if *mint.owner != spl_token_2022::id() {
+        return err!(HolderMetadataPluginError::NotGroupMember);
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let member_address: Option<Pubkey> = mint_state
+        .get_extension::<GroupMemberPointer>()
+        .ok()
+        .and_then(|pointer| pointer.member_address.into());
+    let is_member = member_address == Some(*mint.key)
+        && mint_state
+            .get_extension::<TokenGroupMember>()
+            .is_ok_and(|member| member.mint == *mint.key && member.group == *group);
+    if !is_member {
+        return err!(HolderMetadataPluginError::NotGroupMember);
+    }
+
+    Ok(())
+}
+
+/// Returns whether the metadata stores its field authorities in a TLV entry (v2) rather than in
+/// field PDAs (v1)
+pub fn has_field_authorities(metadata: &AccountInfo) -> Result<bool> {
+    let buffer = metadata.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    Ok(state.get_first_bytes::<FieldAuthorities>().is_ok())
+}
+
+/// Returns whether the authority may update the field, through the v1 field PDA when given, or
+/// the v2 field authorities of the metadata otherwise
+pub fn is_field_authority(
+    metadata: &AccountInfo,
+    field_pda: Option<&AccountInfo>,
+    authority: &Pubkey,
+    field: &AnchorField,
+) -> Result<bool> {
+    if let Some(field_pda) = field_pda {
+        let field_pda_data = field_pda.try_borrow_data()?;
+        return Ok(
+            try_from_slice_unchecked::<FieldAuthorityAccount>(&field_pda_data)
+                .is_ok_and(|field_pda_data| field_pda_data.authority == *authority),
+        );
+    }
+
+    let buffer = metadata.try_borrow_data()?;
+    let state = TlvStateBorrowed::unpack(&buffer)?;
+    let Ok(field_authorities) = state.get_first_variable_len_value::<FieldAuthorities>() else {
+        return Ok(false);
+    };
+    Ok(field_authorities.contains_field_authority(FieldAuthority {
+        field: field.clone().into(),
+        authority: *authority,
+    }))
+}
+
+/// Checks the token account is in the middle of a transfer of a mint hooked into this program,
+/// so the transfer hook can't be called directly to clear holder fields
+pub fn check_transferring(source_token: &AccountInfo, mint: &AccountInfo) -> Result<()> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    if transfer_hook::get_program_id(&mint_state) != Some(crate::id()) {
+        return err!(HolderMetadataPluginError::TransferHookMismatch);
+    }
+
+    let source_data = source_token.try_borrow_data()?;
+    let source_state = StateWithExtensions::<TokenAccountState>::unpack(&source_data)?;
+    let transferring = source_state
+        .get_extension::<TransferHookAccount>()
+        .is_ok_and(|extension| bool::from(extension.transferring));
+    if !transferring {
+        return err!(HolderMetadataPluginError::NotTransferring);
+    }
+
+    Ok(())
+}
+
+/// Returns the current balance of the top holder's token account, 0 once it's closed or
+/// reopened for another mint
+pub fn get_top_holder_balance(
+    top_holder_token_account: Option<&AccountInfo>,
+    expected: &Pubkey,
+    mint: &Pubkey,
+) -> Result<u64> {
+    let Some(top_holder_token_account) =
+        top_holder_token_account.filter(|account| account.key == expected)
+    else {
+        return err!(HolderMetadataPluginError::TopHolderAccountMismatch);
+    };
+    if top_holder_token_account.data_is_empty() {
+        return Ok(0);
+    }
+
+    // Token-2022 accounts share the base layout of SPL Token accounts
+    let owner = top_holder_token_account.owner;
+    if *owner != spl_token_2022::id() && *owner != anchor_spl::token::ID {
+        return err!(HolderMetadataPluginError::TopHolderAccountMismatch);
+    }
+    let data = top_holder_token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+    if state.base.mint != *mint {
+        return Ok(0);
+    }
+    Ok(state.base.amount)
+}
+
+/// Checks the field can be written through the global PDA. The global PDA is shared by every
+/// collection, so it only ever writes additional keys.
+pub fn check_global_pda_field(field: &AnchorField) -> Result<()> {
+    HolderFields::AllKeys.check_field(field)
+}
+
+/// Checks the holder signed, or a live session key of the holder signed for the field
+pub fn check_holder_signer(
+    holder: &AccountInfo,
+    holder_session: Option<&Account<HolderSession>>,
+    session_key: Option<&Signer>,
+    field: &AnchorField,
+) -> Result<()> {
+    if holder.is_signer {
+        return Ok(());
+    }
+
+    let (Some(holder_session), Some(session_key)) = (holder_session, session_key) else {
+        return err!(HolderMetadataPluginError::HolderNotSigner);
+    };
+    if holder_session.holder != *holder.key || holder_session.session_key != session_key.key() {
+        return err!(HolderMetadataPluginError::HolderNotSigner);
+    }
+    if Clock::get()?.unix_timestamp >= holder_session.expires_at {
+        return err!(HolderMetadataPluginError::SessionExpired);
+    }
+    holder_session.fields.check_field(field)?;
+
+    Ok(())
+}
+
+/// Closes a program owned account, sending its lamports to the destination
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? += lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&System::id());
+    account.realloc(0, false)?;
+    Ok(())
+}
@@ -1,8 +1,21 @@
-use crate::constants::HOLDER_METADATA_PDA_SEED;
+use crate::constants::{
+    ATM_PROGRAM_ID, EXTRA_ACCOUNT_METAS_SEED, HOLDER_BAN_SEED, HOLDER_FIELD_STATE_SEED,
+    HOLDER_METADATA_PDA_SEED, HOLDER_POLICY_SEED, HOLDER_POLICY_SIGNER_SEED, HOLDER_SESSION_SEED,
+    HOLDER_VALUE_CLAIM_SEED,
+};
+use crate::helpers::hash_holder_value;
+use crate::state::{
+    HolderBan, HolderFieldState, HolderFieldUpdate, HolderFields, HolderPolicy, HolderPolicyConfig,
+    HolderSession, HolderValueClaim,
+};
 use gpl_common::AnchorField;
 
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::{
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
 
 #[derive(Accounts)]
 #[instruction(field: AnchorField, val: String)]
@@ -11,8 +24,8 @@ pub struct UpdateHolderField<'info> {
     #[account(mut)]
     pub holder: Signer<'info>,
     pub mint: InterfaceAccount<'info, Mint>,
-    /// CHECK: Account checked in CPI
-    #[account(mut)]
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
     pub metadata: UncheckedAccount<'info>,
     #[account(
         associated_token::token_program = token_program,
@@ -27,7 +40,8 @@ pub struct UpdateHolderField<'info> {
     /// CHECK: Account checked in CPI
     pub field_pda: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
-    /// CHECK: Account checked in CPI
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
     pub field_authority_program: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -37,10 +51,41 @@ pub struct UpdateHolderField<'info> {
 pub struct UpdateHolderFieldV2<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub holder: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
     pub mint: InterfaceAccount<'info, Mint>,
-    /// CHECK: Account checked in CPI
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        associated_token::token_program = token_program,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        constraint = holder_token_account.amount > 0,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Account checked in constraints
+    #[account(seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()], bump)]
+    pub holder_metadata_pda: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(field: AnchorField, val: String)]
+pub struct UpdateHolderFieldUnified<'info> {
     #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
     pub metadata: UncheckedAccount<'info>,
     #[account(
         associated_token::token_program = token_program,
@@ -53,7 +98,383 @@ pub struct UpdateHolderFieldV2<'info> {
     #[account(seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()], bump)]
     pub holder_metadata_pda: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
-    /// CHECK: Account checked in CPI
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Account checked in CPI, only needed when the metadata uses v1 field PDAs
+    pub field_pda: Option<UncheckedAccount<'info>>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(updates: Vec<HolderFieldUpdate>)]
+pub struct UpdateHolderFields<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        associated_token::token_program = token_program,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        constraint = holder_token_account.amount > 0,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Account checked in constraints
+    #[account(seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()], bump)]
+    pub holder_metadata_pda: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(field: AnchorField, val: String)]
+pub struct UpdateHolderFieldEmbedded<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
+    // Stores the metadata itself, update authority checked in CPI
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        associated_token::token_program = token_program,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        constraint = holder_token_account.amount > 0,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Account checked in constraints
+    #[account(seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()], bump)]
+    pub holder_metadata_pda: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(field: AnchorField)]
+pub struct RemoveHolderField<'info> {
+    // Receives the rent freed by the removal
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        associated_token::token_program = token_program,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        constraint = holder_token_account.amount > 0,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Account checked in constraints
+    #[account(seeds = [HOLDER_METADATA_PDA_SEED.as_bytes()], bump)]
+    pub holder_metadata_pda: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
     pub field_authority_program: UncheckedAccount<'info>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(config: HolderPolicyConfig)]
+pub struct CreateHolderPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = HolderPolicy::space(&config),
+        seeds = [HOLDER_POLICY_SEED.as_bytes(), update_authority.key().as_ref()],
+        bump,
+    )]
+    pub holder_policy: Account<'info, HolderPolicy>,
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+#[instruction(config: HolderPolicyConfig)]
+pub struct UpdateHolderPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = update_authority,
+        realloc = HolderPolicy::space(&config),
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds = [HOLDER_POLICY_SEED.as_bytes(), update_authority.key().as_ref()],
+        bump,
+    )]
+    pub holder_policy: Account<'info, HolderPolicy>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(field: AnchorField, val: String)]
+pub struct UpdateHolderFieldWithPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Holder or session key signature checked in handler
+    pub holder: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    // Checked in handler, policies may allow more than the associated token account
+    #[account(token::mint = mint, token::token_program = token_program)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub holder_policy: Account<'info, HolderPolicy>,
+    /// CHECK: Account checked in constraints
+    #[account(
+        seeds = [HOLDER_POLICY_SIGNER_SEED.as_bytes(), holder_policy.key().as_ref()],
+        bump = holder_policy.signer_bump,
+    )]
+    pub policy_signer: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = HolderFieldState::LEN,
+        seeds = [
+            HOLDER_FIELD_STATE_SEED.as_bytes(),
+            holder_policy.key().as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub holder_field_state: Account<'info, HolderFieldState>,
+    /// CHECK: Account checked in constraints, the mint is banned when it exists
+    #[account(
+        seeds = [HOLDER_BAN_SEED.as_bytes(), holder_policy.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub mint_ban: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints, the holder is banned when it exists
+    #[account(
+        seeds = [HOLDER_BAN_SEED.as_bytes(), holder_policy.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_ban: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints, the token account's owner is banned when it exists,
+    /// so a banned owner can't edit through a delegate
+    #[account(
+        seeds = [
+            HOLDER_BAN_SEED.as_bytes(),
+            holder_policy.key().as_ref(),
+            holder_token_account.owner.as_ref(),
+        ],
+        bump,
+    )]
+    pub owner_ban: UncheckedAccount<'info>,
+    /// CHECK: Account checked in CPI, only passed when the field was granted with v1
+    pub field_pda: Option<UncheckedAccount<'info>>,
+    /// CHECK: Account checked in handler, only needed to override the top holder
+    pub top_holder_token_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: Account checked in constraints
+    #[account(mut, address = holder_policy.config.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+    // Only needed for token fees, checked in handler
+    pub fee_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub payer_fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub fee_token_program: Option<Interface<'info, TokenInterface>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub holder_session: Option<Account<'info, HolderSession>>,
+    pub session_key: Option<Signer<'info>>,
+    // Only needed when the policy makes values unique, checked in handler
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = HolderValueClaim::LEN,
+        seeds = [
+            HOLDER_VALUE_CLAIM_SEED.as_bytes(),
+            holder_policy.key().as_ref(),
+            &field.seed(),
+            &hash_holder_value(&val),
+        ],
+        bump,
+    )]
+    pub value_claim: Option<Account<'info, HolderValueClaim>>,
+    /// CHECK: Account checked in handler, the claim of the value being replaced
+    #[account(mut)]
+    pub previous_value_claim: Option<UncheckedAccount<'info>>,
+    /// CHECK: Account checked in handler, metadata of another mint claiming the value
+    pub claimed_metadata: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHolderTransferHook<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(has_one = update_authority)]
+    pub holder_policy: Account<'info, HolderPolicy>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in constraints, mint checked in handler
+    #[account(owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints, data written in handler
+    #[account(
+        init,
+        payer = payer,
+        // Metadata, policy, policy signer and field authority program, then a field PDA per field
+        space = ExtraAccountMetaList::size_of(4 + holder_policy.config.fields.listed().len())?,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED.as_bytes(), mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHolderTransferHook<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub update_authority: Signer<'info>,
+    #[account(has_one = update_authority)]
+    pub holder_policy: Account<'info, HolderPolicy>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Account checked in constraints, mint checked in handler
+    #[account(owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints, resized and written in handler
+    #[account(
+        mut,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED.as_bytes(), mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts follow the transfer hook interface, extras come from the extra account meta list
+#[derive(Accounts)]
+pub struct ExecuteTransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Source token account's owner or delegate
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints
+    #[account(seeds = [EXTRA_ACCOUNT_METAS_SEED.as_bytes(), mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: Account checked in CPI, mint checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    pub holder_policy: Account<'info, HolderPolicy>,
+    /// CHECK: Account checked in constraints
+    #[account(
+        seeds = [HOLDER_POLICY_SIGNER_SEED.as_bytes(), holder_policy.key().as_ref()],
+        bump = holder_policy.signer_bump,
+    )]
+    pub policy_signer: UncheckedAccount<'info>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(field: AnchorField)]
+pub struct ModerateHolderField<'info> {
+    pub moderator: Signer<'info>,
+    /// CHECK: Account checked in CPI, update authority checked in handler
+    #[account(mut, owner = ATM_PROGRAM_ID)]
+    pub metadata: UncheckedAccount<'info>,
+    pub holder_policy: Account<'info, HolderPolicy>,
+    /// CHECK: Account checked in constraints
+    #[account(
+        seeds = [HOLDER_POLICY_SIGNER_SEED.as_bytes(), holder_policy.key().as_ref()],
+        bump = holder_policy.signer_bump,
+    )]
+    pub policy_signer: UncheckedAccount<'info>,
+    /// CHECK: Account checked in CPI, only passed when the field was granted with v1
+    pub field_pda: Option<UncheckedAccount<'info>>,
+    /// CHECK: Account checked in constraints
+    #[account(address = ATM_PROGRAM_ID)]
+    pub field_authority_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(target: Pubkey)]
+pub struct BanHolder<'info> {
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+    pub holder_policy: Account<'info, HolderPolicy>,
+    #[account(
+        init,
+        payer = moderator,
+        space = HolderBan::LEN,
+        seeds = [HOLDER_BAN_SEED.as_bytes(), holder_policy.key().as_ref(), target.as_ref()],
+        bump,
+    )]
+    pub holder_ban: Account<'info, HolderBan>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LiftHolderBan<'info> {
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+    pub holder_policy: Account<'info, HolderPolicy>,
+    #[account(mut, has_one = holder_policy, close = moderator)]
+    pub holder_ban: Account<'info, HolderBan>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey, fields: HolderFields)]
+pub struct CreateHolderSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub holder: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = HolderSession::space(&fields),
+        seeds = [HOLDER_SESSION_SEED.as_bytes(), holder.key().as_ref(), session_key.as_ref()],
+        bump,
+    )]
+    pub holder_session: Account<'info, HolderSession>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseHolderSession<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    #[account(mut, has_one = holder, close = holder)]
+    pub holder_session: Account<'info, HolderSession>,
+}
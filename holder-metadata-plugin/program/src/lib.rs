@@ -1,6 +1,9 @@
 pub mod constants;
+pub mod errors;
+pub mod helpers;
 pub mod instructions;
 pub mod processor;
+pub mod state;
 
 pub use constants::HOLDER_METADATA_PDA_SEED;
 
@@ -9,6 +12,7 @@ use processor::*;
 
 use anchor_lang::prelude::*;
 use gpl_common::AnchorField;
+use state::{HolderFieldUpdate, HolderFields, HolderPolicyConfig};
 
 declare_id!("3DkEmKWuBJbza9ur1BnVVhXrzkuiMCqBuKHdoDBdLpxZ");
 
@@ -16,6 +20,7 @@ declare_id!("3DkEmKWuBJbza9ur1BnVVhXrzkuiMCqBuKHdoDBdLpxZ");
 pub mod holder_metadata_plugin {
     use super::*;
 
+    /// Deprecated: use `update_holder_field_unified`, which detects v1 or v2 field authorities
     pub fn update_holder_field(
         ctx: Context<UpdateHolderField>,
         field: AnchorField,
@@ -24,6 +29,7 @@ pub mod holder_metadata_plugin {
         return handle_update_holder_field(ctx, field, val);
     }
 
+    /// Deprecated: use `update_holder_field_unified`, which detects v1 or v2 field authorities
     pub fn update_holder_field_v2(
         ctx: Context<UpdateHolderFieldV2>,
         field: AnchorField,
@@ -31,4 +37,99 @@ pub mod holder_metadata_plugin {
     ) -> Result<()> {
         return handle_update_holder_field_v2(ctx, field, val);
     }
+
+    pub fn update_holder_field_unified(
+        ctx: Context<UpdateHolderFieldUnified>,
+        field: AnchorField,
+        val: String,
+    ) -> Result<()> {
+        return handle_update_holder_field_unified(ctx, field, val);
+    }
+
+    pub fn update_holder_fields<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateHolderFields<'info>>,
+        updates: Vec<HolderFieldUpdate>,
+    ) -> Result<()> {
+        return handle_update_holder_fields(ctx, updates);
+    }
+
+    pub fn update_holder_field_embedded(
+        ctx: Context<UpdateHolderFieldEmbedded>,
+        field: AnchorField,
+        val: String,
+    ) -> Result<()> {
+        return handle_update_holder_field_embedded(ctx, field, val);
+    }
+
+    pub fn remove_holder_field(ctx: Context<RemoveHolderField>, field: AnchorField) -> Result<()> {
+        return handle_remove_holder_field(ctx, field);
+    }
+
+    pub fn create_holder_policy(
+        ctx: Context<CreateHolderPolicy>,
+        config: HolderPolicyConfig,
+    ) -> Result<()> {
+        return handle_create_holder_policy(ctx, config);
+    }
+
+    pub fn update_holder_policy(
+        ctx: Context<UpdateHolderPolicy>,
+        config: HolderPolicyConfig,
+    ) -> Result<()> {
+        return handle_update_holder_policy(ctx, config);
+    }
+
+    pub fn update_holder_field_with_policy(
+        ctx: Context<UpdateHolderFieldWithPolicy>,
+        field: AnchorField,
+        val: String,
+    ) -> Result<()> {
+        return handle_update_holder_field_with_policy(ctx, field, val);
+    }
+
+    pub fn moderate_holder_field(
+        ctx: Context<ModerateHolderField>,
+        field: AnchorField,
+    ) -> Result<()> {
+        return handle_moderate_holder_field(ctx, field);
+    }
+
+    pub fn ban_holder(ctx: Context<BanHolder>, target: Pubkey) -> Result<()> {
+        return handle_ban_holder(ctx, target);
+    }
+
+    pub fn lift_holder_ban(ctx: Context<LiftHolderBan>) -> Result<()> {
+        return handle_lift_holder_ban(ctx);
+    }
+
+    pub fn create_holder_session(
+        ctx: Context<CreateHolderSession>,
+        session_key: Pubkey,
+        fields: HolderFields,
+        expires_at: i64,
+    ) -> Result<()> {
+        return handle_create_holder_session(ctx, session_key, fields, expires_at);
+    }
+
+    pub fn close_holder_session(ctx: Context<CloseHolderSession>) -> Result<()> {
+        return handle_close_holder_session(ctx);
+    }
+
+    pub fn initialize_holder_transfer_hook(
+        ctx: Context<InitializeHolderTransferHook>,
+    ) -> Result<()> {
+        return handle_initialize_holder_transfer_hook(ctx);
+    }
+
+    pub fn update_holder_transfer_hook(ctx: Context<UpdateHolderTransferHook>) -> Result<()> {
+        return handle_update_holder_transfer_hook(ctx);
+    }
+
+    #[interface(spl_transfer_hook_interface::execute)]
+    pub fn execute_transfer_hook<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransferHook<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        return handle_execute_transfer_hook(ctx, amount);
+    }
 }
@@ -1,18 +1,48 @@
-use crate::constants::HOLDER_METADATA_PDA_SEED;
+use crate::constants::MAX_HOLDER_SESSION_SECONDS;
+use crate::constants::{
+    HOLDER_METADATA_PDA_SEED, HOLDER_POLICY_SIGNER_SEED, HOLDER_VALUE_CLAIM_SEED,
+};
+use crate::errors::HolderMetadataPluginError;
+use crate::helpers::{
+    check_global_pda_field, check_group_member, check_holder_signer, check_metadata_mint,
+    check_policy_update_authority, check_transferring, close_account, get_embedded_metadata,
+    get_field_value, get_token_metadata, get_top_holder_balance, has_field_authorities,
+    hash_holder_value, is_field_authority,
+};
 use crate::instructions::*;
+use crate::state::{
+    HolderFee, HolderFieldUpdate, HolderFields, HolderPolicy, HolderPolicyConfig, HolderValueClaim,
+};
 
-use anchor_lang::{prelude::*, solana_program::program::invoke_signed};
+use anchor_lang::{
+    prelude::*,
+    solana_program::program::invoke_signed,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::{transfer_checked, TransferChecked};
 use field_authority_interface::{
+    find_field_pda,
     instructions::update_field_with_field_authority,
-    instructions_v2::update_field_with_field_authority_v2,
+    instructions_v2::{remove_key_with_field_authority_v2, update_field_with_field_authority_v2},
+    normalize_key, FieldSeedVersion,
 };
 use gpl_common::{reach_minimum_rent, AnchorField};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+use spl_token_metadata_interface::{instruction::update_field, state::Field};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
 
 pub fn handle_update_holder_field(
     ctx: Context<UpdateHolderField>,
     field: AnchorField,
     val: String,
 ) -> Result<()> {
+    check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    check_global_pda_field(&field)?;
+
     // Update field with field authority
     let ix = &update_field_with_field_authority(
         ctx.accounts.field_authority_program.key,
@@ -46,6 +76,20 @@ pub fn handle_update_holder_field_v2(
     field: AnchorField,
     val: String,
 ) -> Result<()> {
+    check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    check_holder_signer(
+        &ctx.accounts.holder,
+        ctx.accounts.holder_session.as_ref(),
+        ctx.accounts.session_key.as_ref(),
+        &field,
+    )?;
+
+    check_global_pda_field(&field)?;
+
     // Update field with field authority
     let ix = &update_field_with_field_authority_v2(
         ctx.accounts.field_authority_program.key,
@@ -71,3 +115,775 @@ pub fn handle_update_holder_field_v2(
 
     Ok(())
 }
+
+pub fn handle_update_holder_field_unified(
+    ctx: Context<UpdateHolderFieldUnified>,
+    field: AnchorField,
+    val: String,
+) -> Result<()> {
+    check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    check_holder_signer(
+        &ctx.accounts.holder,
+        ctx.accounts.holder_session.as_ref(),
+        ctx.accounts.session_key.as_ref(),
+        &field,
+    )?;
+
+    check_global_pda_field(&field)?;
+
+    // Update field with field authority, taking the path matching how authorities are stored
+    let metadata = ctx.accounts.metadata.to_account_info();
+    let field_pda = if has_field_authorities(&metadata)? {
+        None
+    } else {
+        let Some(field_pda) = &ctx.accounts.field_pda else {
+            return err!(HolderMetadataPluginError::FieldPdaMissing);
+        };
+        Some(field_pda.as_ref())
+    };
+    invoke_update_as_holder_metadata_pda(
+        &ctx.accounts.field_authority_program,
+        &metadata,
+        &ctx.accounts.holder_metadata_pda,
+        ctx.bumps.holder_metadata_pda,
+        field_pda,
+        field,
+        val,
+    )?;
+
+    reach_minimum_rent(ctx.accounts.payer.clone(), metadata)?;
+
+    Ok(())
+}
+
+pub fn handle_update_holder_fields<'info>(
+    ctx: Context<'_, '_, '_, 'info, UpdateHolderFields<'info>>,
+    updates: Vec<HolderFieldUpdate>,
+) -> Result<()> {
+    check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    for update in updates.iter() {
+        check_holder_signer(
+            &ctx.accounts.holder,
+            ctx.accounts.holder_session.as_ref(),
+            ctx.accounts.session_key.as_ref(),
+            &update.field,
+        )?;
+
+        check_global_pda_field(&update.field)?;
+    }
+
+    // v1 field PDAs are passed as remaining accounts, one per update in the same order
+    let metadata = ctx.accounts.metadata.to_account_info();
+    let uses_field_pdas = !has_field_authorities(&metadata)?;
+    if uses_field_pdas && ctx.remaining_accounts.len() < updates.len() {
+        return err!(HolderMetadataPluginError::FieldPdaMissing);
+    }
+
+    for (i, update) in updates.into_iter().enumerate() {
+        invoke_update_as_holder_metadata_pda(
+            &ctx.accounts.field_authority_program,
+            &metadata,
+            &ctx.accounts.holder_metadata_pda,
+            ctx.bumps.holder_metadata_pda,
+            uses_field_pdas.then(|| &ctx.remaining_accounts[i]),
+            update.field,
+            update.val,
+        )?;
+    }
+
+    // Top up rent once for all the updates
+    reach_minimum_rent(ctx.accounts.payer.clone(), metadata)?;
+
+    Ok(())
+}
+
+pub fn handle_update_holder_field_embedded(
+    ctx: Context<UpdateHolderFieldEmbedded>,
+    field: AnchorField,
+    val: String,
+) -> Result<()> {
+    let mint = ctx.accounts.mint.to_account_info();
+    get_embedded_metadata(&mint)?;
+
+    check_holder_signer(
+        &ctx.accounts.holder,
+        ctx.accounts.holder_session.as_ref(),
+        ctx.accounts.session_key.as_ref(),
+        &field,
+    )?;
+
+    check_global_pda_field(&field)?;
+
+    // Token-2022 only knows the metadata's update authority, which must be the global PDA
+    let ix = &update_field(
+        ctx.accounts.token_program.key,
+        mint.key,
+        &ctx.accounts.holder_metadata_pda.key(),
+        field.into(),
+        val,
+    );
+    let account_infos = &[
+        mint.clone(),
+        ctx.accounts.holder_metadata_pda.to_account_info(),
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOLDER_METADATA_PDA_SEED.as_bytes(),
+        &[ctx.bumps.holder_metadata_pda],
+    ]];
+    invoke_signed(ix, account_infos, signer_seeds)?;
+
+    reach_minimum_rent(ctx.accounts.payer.clone(), mint)?;
+
+    Ok(())
+}
+
+pub fn handle_remove_holder_field(
+    ctx: Context<RemoveHolderField>,
+    field: AnchorField,
+) -> Result<()> {
+    check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    check_holder_signer(
+        &ctx.accounts.holder,
+        ctx.accounts.holder_session.as_ref(),
+        ctx.accounts.session_key.as_ref(),
+        &field,
+    )?;
+
+    // Like updates through the global PDA, only additional keys can be removed
+    let AnchorField::Key(key) = field else {
+        return err!(HolderMetadataPluginError::FieldNotAllowed);
+    };
+
+    // Remove key with field authority, refunding the freed rent to the payer
+    let ix = &remove_key_with_field_authority_v2(
+        ctx.accounts.field_authority_program.key,
+        ctx.accounts.metadata.key,
+        &ctx.accounts.holder_metadata_pda.key(),
+        ctx.accounts.payer.key,
+        key,
+        false, // idempotent
+    );
+    let account_infos = &[
+        ctx.accounts.metadata.to_account_info(),
+        ctx.accounts.holder_metadata_pda.to_account_info(),
+        ctx.accounts.payer.to_account_info(),
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOLDER_METADATA_PDA_SEED.as_bytes(),
+        &[ctx.bumps.holder_metadata_pda],
+    ]];
+    invoke_signed(ix, account_infos, signer_seeds)?;
+
+    Ok(())
+}
+
+pub fn handle_create_holder_policy(
+    ctx: Context<CreateHolderPolicy>,
+    config: HolderPolicyConfig,
+) -> Result<()> {
+    let holder_policy_key = ctx.accounts.holder_policy.key();
+    let (_, signer_bump) = Pubkey::find_program_address(
+        &[
+            HOLDER_POLICY_SIGNER_SEED.as_bytes(),
+            holder_policy_key.as_ref(),
+        ],
+        ctx.program_id,
+    );
+
+    config.validate()?;
+    let holder_policy = &mut ctx.accounts.holder_policy;
+    holder_policy.update_authority = ctx.accounts.update_authority.key();
+    holder_policy.signer_bump = signer_bump;
+    holder_policy.config = config;
+
+    Ok(())
+}
+
+pub fn handle_update_holder_policy(
+    ctx: Context<UpdateHolderPolicy>,
+    config: HolderPolicyConfig,
+) -> Result<()> {
+    config.validate()?;
+    ctx.accounts.holder_policy.config = config;
+
+    Ok(())
+}
+
+/// Updates a field signed by the policy signer, with the v1 field PDA if the field was granted
+/// with v1 and through the metadata's field authorities otherwise
+fn invoke_update_as_holder_metadata_pda<'info>(
+    field_authority_program: &AccountInfo<'info>,
+    metadata: &AccountInfo<'info>,
+    holder_metadata_pda: &AccountInfo<'info>,
+    holder_metadata_pda_bump: u8,
+    field_pda: Option<&AccountInfo<'info>>,
+    field: AnchorField,
+    val: String,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOLDER_METADATA_PDA_SEED.as_bytes(),
+        &[holder_metadata_pda_bump],
+    ]];
+    match field_pda {
+        Some(field_pda) => {
+            let ix = &update_field_with_field_authority(
+                field_authority_program.key,
+                metadata.key,
+                holder_metadata_pda.key,
+                field.into(),
+                val,
+            );
+            let account_infos = &[
+                metadata.clone(),
+                holder_metadata_pda.clone(),
+                field_pda.clone(),
+            ];
+            invoke_signed(ix, account_infos, signer_seeds)?;
+        }
+        None => {
+            let ix = &update_field_with_field_authority_v2(
+                field_authority_program.key,
+                metadata.key,
+                holder_metadata_pda.key,
+                field.into(),
+                val,
+            );
+            let account_infos = &[metadata.clone(), holder_metadata_pda.clone()];
+            invoke_signed(ix, account_infos, signer_seeds)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn invoke_update_as_policy_signer<'info>(
+    field_authority_program: &AccountInfo<'info>,
+    metadata: &AccountInfo<'info>,
+    policy_signer: &AccountInfo<'info>,
+    field_pda: Option<&AccountInfo<'info>>,
+    holder_policy: &Account<'info, HolderPolicy>,
+    field: AnchorField,
+    val: String,
+) -> Result<()> {
+    let holder_policy_key = holder_policy.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOLDER_POLICY_SIGNER_SEED.as_bytes(),
+        holder_policy_key.as_ref(),
+        &[holder_policy.signer_bump],
+    ]];
+    match field_pda {
+        Some(field_pda) => {
+            let ix = &update_field_with_field_authority(
+                field_authority_program.key,
+                metadata.key,
+                policy_signer.key,
+                field.into(),
+                val,
+            );
+            let account_infos = &[metadata.clone(), policy_signer.clone(), field_pda.clone()];
+            invoke_signed(ix, account_infos, signer_seeds)?;
+        }
+        None => {
+            let ix = &update_field_with_field_authority_v2(
+                field_authority_program.key,
+                metadata.key,
+                policy_signer.key,
+                field.into(),
+                val,
+            );
+            let account_infos = &[metadata.clone(), policy_signer.clone()];
+            invoke_signed(ix, account_infos, signer_seeds)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Claims the new value for the mint, taking it over from a mint that no longer holds it, and
+/// releases the claim of the value it replaces
+fn update_value_claims(
+    ctx: &mut Context<UpdateHolderFieldWithPolicy>,
+    field: &AnchorField,
+    current: &str,
+    val: &str,
+) -> Result<()> {
+    let holder_policy_key = ctx.accounts.holder_policy.key();
+    let mint_key = ctx.accounts.mint.key();
+
+    // Empty values reset the field, so they're never claimed
+    if !normalize_key(val).is_empty() {
+        let Some(value_claim) = ctx.accounts.value_claim.as_mut() else {
+            return err!(HolderMetadataPluginError::ValueClaimMismatch);
+        };
+        if value_claim.mint != Pubkey::default() && value_claim.mint != mint_key {
+            // Claims aren't released when a transfer or moderator resets the value
+            let Some(claimed_metadata) = &ctx.accounts.claimed_metadata else {
+                return err!(HolderMetadataPluginError::ValueTaken);
+            };
+            if claimed_metadata.key() != value_claim.metadata
+                || *claimed_metadata.owner != ctx.accounts.field_authority_program.key()
+            {
+                return err!(HolderMetadataPluginError::ValueClaimMismatch);
+            }
+            let claimed_value = get_field_value(&get_token_metadata(claimed_metadata)?, field);
+            if hash_holder_value(&claimed_value) == hash_holder_value(val) {
+                return err!(HolderMetadataPluginError::ValueTaken);
+            }
+        }
+        value_claim.holder_policy = holder_policy_key;
+        value_claim.mint = mint_key;
+        value_claim.metadata = ctx.accounts.metadata.key();
+    }
+
+    // Release the claim of the replaced value, unless another mint took it over
+    if !normalize_key(current).is_empty() && hash_holder_value(current) != hash_holder_value(val) {
+        let (previous_value_claim_key, _) = Pubkey::find_program_address(
+            &[
+                HOLDER_VALUE_CLAIM_SEED.as_bytes(),
+                holder_policy_key.as_ref(),
+                &field.seed(),
+                &hash_holder_value(current),
+            ],
+            ctx.program_id,
+        );
+        let Some(previous_value_claim) = ctx
+            .accounts
+            .previous_value_claim
+            .as_ref()
+            .filter(|claim| claim.key() == previous_value_claim_key)
+        else {
+            return err!(HolderMetadataPluginError::ValueClaimMismatch);
+        };
+        // Values set before the policy made them unique have no claim
+        if previous_value_claim.owner == ctx.program_id {
+            let mint = {
+                let data = previous_value_claim.try_borrow_data()?;
+                HolderValueClaim::try_deserialize(&mut data.as_ref())?.mint
+            };
+            if mint == mint_key {
+                close_account(previous_value_claim, &ctx.accounts.payer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn pay_token_fee(
+    ctx: &Context<UpdateHolderFieldWithPolicy>,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let (
+        Some(fee_mint),
+        Some(payer_fee_token_account),
+        Some(treasury_fee_token_account),
+        Some(fee_token_program),
+    ) = (
+        &ctx.accounts.fee_mint,
+        &ctx.accounts.payer_fee_token_account,
+        &ctx.accounts.treasury_fee_token_account,
+        &ctx.accounts.fee_token_program,
+    )
+    else {
+        return err!(HolderMetadataPluginError::FeeAccountsMissing);
+    };
+
+    if fee_mint.key() != mint {
+        return err!(HolderMetadataPluginError::FeeMintMismatch);
+    }
+    if treasury_fee_token_account.mint != mint
+        || treasury_fee_token_account.owner != ctx.accounts.treasury.key()
+    {
+        return err!(HolderMetadataPluginError::FeeTreasuryMismatch);
+    }
+
+    // Checked transfers work for both token programs
+    let cpi_ctx = CpiContext::new(
+        fee_token_program.to_account_info(),
+        TransferChecked {
+            from: payer_fee_token_account.to_account_info(),
+            mint: fee_mint.to_account_info(),
+            to: treasury_fee_token_account.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        },
+    );
+    transfer_checked(cpi_ctx, amount, fee_mint.decimals)?;
+
+    Ok(())
+}
+
+pub fn handle_update_holder_field_with_policy(
+    mut ctx: Context<UpdateHolderFieldWithPolicy>,
+    field: AnchorField,
+    val: String,
+) -> Result<()> {
+    // Group policies let holders of any member mint write to the group's own metadata, other
+    // metadata granting the policy signer stays tied to its mint
+    let holder_policy = &ctx.accounts.holder_policy;
+    let metadata = ctx.accounts.metadata.to_account_info();
+    let mint = ctx.accounts.mint.to_account_info();
+    let token_metadata = match holder_policy.config.holders.group {
+        Some(group) => {
+            check_group_member(&mint, &group)?;
+            let token_metadata = get_token_metadata(&metadata)?;
+            if token_metadata.mint != group {
+                return err!(HolderMetadataPluginError::NotGroupMetadata);
+            }
+            token_metadata
+        }
+        None => check_metadata_mint(&metadata, &mint)?,
+    };
+
+    check_policy_update_authority(&token_metadata, holder_policy)?;
+
+    check_holder_signer(
+        &ctx.accounts.holder,
+        ctx.accounts.holder_session.as_ref(),
+        ctx.accounts.session_key.as_ref(),
+        &field,
+    )?;
+
+    // Moderators can ban mints and wallets from editing
+    if !ctx.accounts.mint_ban.data_is_empty()
+        || !ctx.accounts.holder_ban.data_is_empty()
+        || !ctx.accounts.owner_ban.data_is_empty()
+    {
+        return err!(HolderMetadataPluginError::HolderBanned);
+    }
+
+    let balance = holder_policy
+        .config
+        .holders
+        .check_holder(&ctx.accounts.holder_token_account, ctx.accounts.holder.key)?;
+    holder_policy.config.check_update(&field, &val)?;
+
+    // Cooldown
+    let now = Clock::get()?.unix_timestamp;
+    let holder_field_state = &mut ctx.accounts.holder_field_state;
+    if holder_field_state.last_update_ts != 0
+        && now
+            < holder_field_state
+                .last_update_ts
+                .saturating_add(holder_policy.config.cooldown_seconds)
+    {
+        return err!(HolderMetadataPluginError::CooldownActive);
+    }
+    holder_field_state.last_update_ts = now;
+
+    // Top holder wins, until their balance drops below someone else's
+    if holder_policy.config.holders.top_holder_only {
+        let holder_token_account = ctx.accounts.holder_token_account.key();
+        let top_holder_token_account = holder_field_state.top_holder_token_account;
+        if top_holder_token_account != Pubkey::default()
+            && top_holder_token_account != holder_token_account
+        {
+            let top_balance = get_top_holder_balance(
+                ctx.accounts
+                    .top_holder_token_account
+                    .as_ref()
+                    .map(|account| account.as_ref()),
+                &top_holder_token_account,
+                &ctx.accounts.mint.key(),
+            )?;
+            if balance <= top_balance {
+                return err!(HolderMetadataPluginError::NotTopHolder);
+            }
+        }
+        holder_field_state.top_holder_token_account = holder_token_account;
+    }
+
+    // Fee
+    match holder_policy.config.fee {
+        Some(HolderFee::Lamports { amount }) => {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            );
+            transfer(cpi_ctx, amount)?;
+        }
+        Some(HolderFee::Token { mint, amount }) => pay_token_fee(&ctx, mint, amount)?,
+        None => {}
+    }
+
+    // Unique values
+    if holder_policy.config.values.unique {
+        let current = get_field_value(&token_metadata, &field);
+        update_value_claims(&mut ctx, &field, &current, &val)?;
+    }
+
+    // Update field with field authority
+    invoke_update_as_policy_signer(
+        &ctx.accounts.field_authority_program,
+        &ctx.accounts.metadata,
+        &ctx.accounts.policy_signer,
+        ctx.accounts
+            .field_pda
+            .as_ref()
+            .map(|field_pda| field_pda.as_ref()),
+        &ctx.accounts.holder_policy,
+        field,
+        val,
+    )?;
+
+    reach_minimum_rent(
+        ctx.accounts.payer.clone(),
+        ctx.accounts.metadata.to_account_info(),
+    )?;
+
+    Ok(())
+}
+
+pub fn handle_moderate_holder_field(
+    ctx: Context<ModerateHolderField>,
+    field: AnchorField,
+) -> Result<()> {
+    let holder_policy = &ctx.accounts.holder_policy;
+    holder_policy.check_moderator(&ctx.accounts.moderator)?;
+
+    let token_metadata = get_token_metadata(&ctx.accounts.metadata)?;
+    check_policy_update_authority(&token_metadata, holder_policy)?;
+    holder_policy.config.fields.check_field(&field)?;
+
+    // Reset the field like the transfer hook does
+    invoke_update_as_policy_signer(
+        &ctx.accounts.field_authority_program,
+        &ctx.accounts.metadata,
+        &ctx.accounts.policy_signer,
+        ctx.accounts
+            .field_pda
+            .as_ref()
+            .map(|field_pda| field_pda.as_ref()),
+        holder_policy,
+        field,
+        String::new(),
+    )?;
+
+    Ok(())
+}
+
+pub fn handle_ban_holder(ctx: Context<BanHolder>, target: Pubkey) -> Result<()> {
+    ctx.accounts
+        .holder_policy
+        .check_moderator(&ctx.accounts.moderator)?;
+
+    let holder_ban = &mut ctx.accounts.holder_ban;
+    holder_ban.holder_policy = ctx.accounts.holder_policy.key();
+    holder_ban.target = target;
+
+    Ok(())
+}
+
+pub fn handle_lift_holder_ban(ctx: Context<LiftHolderBan>) -> Result<()> {
+    ctx.accounts
+        .holder_policy
+        .check_moderator(&ctx.accounts.moderator)?;
+
+    Ok(())
+}
+
+pub fn handle_create_holder_session(
+    ctx: Context<CreateHolderSession>,
+    session_key: Pubkey,
+    fields: HolderFields,
+    expires_at: i64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    if expires_at <= now || expires_at > now + MAX_HOLDER_SESSION_SECONDS {
+        return err!(HolderMetadataPluginError::InvalidSessionExpiry);
+    }
+
+    let holder_session = &mut ctx.accounts.holder_session;
+    holder_session.holder = ctx.accounts.holder.key();
+    holder_session.session_key = session_key;
+    holder_session.expires_at = expires_at;
+    holder_session.fields = fields;
+
+    Ok(())
+}
+
+pub fn handle_close_holder_session(_ctx: Context<CloseHolderSession>) -> Result<()> {
+    Ok(())
+}
+
+/// Extra accounts of the transfer hook. Everything is known up front, so all extra accounts are
+/// fixed addresses.
+fn get_transfer_hook_extra_account_metas(
+    program_id: &Pubkey,
+    holder_policy: &Account<HolderPolicy>,
+    metadata: &Pubkey,
+    field_authority_program: &Pubkey,
+) -> Result<Vec<ExtraAccountMeta>> {
+    let holder_policy_key = holder_policy.key();
+    let policy_signer = Pubkey::create_program_address(
+        &[
+            HOLDER_POLICY_SIGNER_SEED.as_bytes(),
+            holder_policy_key.as_ref(),
+            &[holder_policy.signer_bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| ProgramError::InvalidSeeds)?;
+    let mut extra_account_metas = vec![
+        ExtraAccountMeta::new_with_pubkey(metadata, false, true)?,
+        ExtraAccountMeta::new_with_pubkey(&holder_policy_key, false, false)?,
+        ExtraAccountMeta::new_with_pubkey(&policy_signer, false, false)?,
+        ExtraAccountMeta::new_with_pubkey(field_authority_program, false, false)?,
+    ];
+    for field in holder_policy.config.fields.listed() {
+        let field: Field = field.clone().into();
+        let version = FieldSeedVersion::for_field(&field);
+        let (field_pda, _bump) =
+            find_field_pda(field_authority_program, metadata, field, version).unwrap();
+        extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(&field_pda, false, false)?);
+    }
+
+    Ok(extra_account_metas)
+}
+
+pub fn handle_initialize_holder_transfer_hook(
+    ctx: Context<InitializeHolderTransferHook>,
+) -> Result<()> {
+    let token_metadata = check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    let holder_policy = &ctx.accounts.holder_policy;
+    check_policy_update_authority(&token_metadata, holder_policy)?;
+
+    let extra_account_metas = get_transfer_hook_extra_account_metas(
+        ctx.program_id,
+        holder_policy,
+        ctx.accounts.metadata.key,
+        ctx.accounts.field_authority_program.key,
+    )?;
+
+    let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas)?;
+
+    Ok(())
+}
+
+pub fn handle_update_holder_transfer_hook(ctx: Context<UpdateHolderTransferHook>) -> Result<()> {
+    let token_metadata = check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    let holder_policy = &ctx.accounts.holder_policy;
+    check_policy_update_authority(&token_metadata, holder_policy)?;
+
+    let extra_account_metas = get_transfer_hook_extra_account_metas(
+        ctx.program_id,
+        holder_policy,
+        ctx.accounts.metadata.key,
+        ctx.accounts.field_authority_program.key,
+    )?;
+
+    // Grow the account before rewriting the list and shrink it after, so the list always fits
+    let extra_account_meta_list = ctx.accounts.extra_account_meta_list.to_account_info();
+    let space = ExtraAccountMetaList::size_of(extra_account_metas.len())?;
+    if space > extra_account_meta_list.data_len() {
+        extra_account_meta_list.realloc(space, false)?;
+        reach_minimum_rent(ctx.accounts.payer.clone(), extra_account_meta_list.clone())?;
+    }
+    {
+        let mut data = extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::update::<ExecuteInstruction>(&mut data, &extra_account_metas)?;
+    }
+    if space < extra_account_meta_list.data_len() {
+        extra_account_meta_list.realloc(space, false)?;
+    }
+
+    Ok(())
+}
+
+pub fn handle_execute_transfer_hook<'info>(
+    ctx: Context<'_, '_, '_, 'info, ExecuteTransferHook<'info>>,
+    amount: u64,
+) -> Result<()> {
+    check_transferring(
+        &ctx.accounts.source_token.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+
+    // Nothing to clear if the token stays with its holder
+    let holder_policy = &ctx.accounts.holder_policy;
+    if !holder_policy.config.clear_on_transfer
+        || amount == 0
+        || ctx.accounts.source_token.owner == ctx.accounts.destination_token.owner
+    {
+        return Ok(());
+    }
+
+    // Clearing is best-effort, so a collection revoking the policy, e.g. by handing the metadata
+    // to another update authority or removing the policy signer's field authority, never blocks
+    // transfers
+    let token_metadata = check_metadata_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.to_account_info(),
+    )?;
+    if check_policy_update_authority(&token_metadata, holder_policy).is_err() {
+        return Ok(());
+    }
+
+    for field in holder_policy.config.fields.listed() {
+        // Only reset values that are set, so fields holders never wrote need no authority. The
+        // empty value is valid for every type of a key schema, so resetting never fails on it.
+        if get_field_value(&token_metadata, field).is_empty() {
+            continue;
+        }
+
+        // Fields granted with v1 have an initialized field PDA
+        let field_authority_program = ctx.accounts.field_authority_program.key();
+        let field_pda = find_field_pda(
+            &field_authority_program,
+            ctx.accounts.metadata.key,
+            field.clone().into(),
+            FieldSeedVersion::for_field(&field.clone().into()),
+        )
+        .and_then(|(field_pda, _bump)| {
+            ctx.remaining_accounts.iter().find(|account| {
+                *account.key == field_pda
+                    && *account.owner == field_authority_program
+                    && !account.data_is_empty()
+            })
+        });
+        if !is_field_authority(
+            &ctx.accounts.metadata,
+            field_pda,
+            ctx.accounts.policy_signer.key,
+            field,
+        )? {
+            continue;
+        }
+
+        invoke_update_as_policy_signer(
+            &ctx.accounts.field_authority_program,
+            &ctx.accounts.metadata,
+            &ctx.accounts.policy_signer,
+            field_pda,
+            holder_policy,
+            field.clone(),
+            String::new(),
+        )?;
+    }
+
+    Ok(())
+}
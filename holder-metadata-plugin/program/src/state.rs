@@ -0,0 +1,298 @@
+use crate::errors::HolderMetadataPluginError;
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{spl_token_2022::state::AccountState, TokenAccount},
+};
+use gpl_common::AnchorField;
+
+/// Fields holders may edit. Name, symbol and URI are only editable when listed explicitly.
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum HolderFields {
+    AllKeys,
+    Only(Vec<AnchorField>),
+}
+
+impl HolderFields {
+    /// Explicitly listed fields, none for all keys
+    pub fn listed(&self) -> &[AnchorField] {
+        match self {
+            HolderFields::AllKeys => &[],
+            HolderFields::Only(fields) => fields,
+        }
+    }
+
+    pub fn check_field(&self, field: &AnchorField) -> Result<()> {
+        let allowed = match self {
+            HolderFields::AllKeys => matches!(field, AnchorField::Key(_)),
+            // Compare seed strings so keys are normalized the same way as field PDAs
+            HolderFields::Only(fields) => fields.iter().any(|f| f.seed_str() == field.seed_str()),
+        };
+        if !allowed {
+            return err!(HolderMetadataPluginError::FieldNotAllowed);
+        }
+        Ok(())
+    }
+}
+
+/// A field and its new value, for updating several fields at once
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct HolderFieldUpdate {
+    pub field: AnchorField,
+    pub val: String,
+}
+
+/// Coarse classes of characters holders may use in values. These aren't Unicode general
+/// categories: letters and numbers follow Rust's `char` predicates, the other two are ASCII only.
+#[derive(Clone, Copy, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum CharClass {
+    Letter,      // Unicode Alphabetic property, any script, including combining marks
+    Number,      // Unicode Nd, Nl and No categories, any script
+    Space,       // ASCII space only, so no tabs or line breaks
+    Punctuation, // ASCII punctuation and symbols, e.g. `!`, `-`, `$` or `~`
+}
+
+impl CharClass {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Number => c.is_numeric(),
+            CharClass::Space => c == ' ',
+            CharClass::Punctuation => c.is_ascii_punctuation(),
+        }
+    }
+}
+
+/// Fee charged per holder edit, paid by the transaction's payer
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum HolderFee {
+    Lamports { amount: u64 },
+    // Sent to the treasury's token account for the mint, Token-2022 mints included
+    Token { mint: Pubkey, amount: u64 },
+}
+
+/// Token accounts that prove holding. By default, only the holder's associated token account.
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct HolderTokenAccounts {
+    pub any_account: bool, // Any account owned by the holder, not only the associated one
+    pub delegates: bool,   // Delegates with a delegated amount may edit for the owner
+    pub frozen: bool,      // Frozen accounts still count
+}
+
+impl HolderTokenAccounts {
+    /// Returns the balance the holder may use, the delegated amount for delegates
+    pub fn check_token_account(
+        &self,
+        token_account: &InterfaceAccount<TokenAccount>,
+        holder: &Pubkey,
+    ) -> Result<u64> {
+        match token_account.state {
+            AccountState::Initialized => {}
+            AccountState::Frozen if self.frozen => {}
+            AccountState::Frozen => return err!(HolderMetadataPluginError::TokenAccountFrozen),
+            AccountState::Uninitialized => return err!(ErrorCode::AccountNotInitialized),
+        }
+
+        let is_owner = token_account.owner == *holder;
+        let is_delegate = self.delegates
+            && Option::<Pubkey>::from(token_account.delegate) == Some(*holder)
+            && token_account.delegated_amount > 0;
+        if !is_owner && !is_delegate {
+            return err!(HolderMetadataPluginError::NotTokenHolder);
+        }
+        let balance = if is_owner {
+            token_account.amount
+        } else {
+            token_account.amount.min(token_account.delegated_amount)
+        };
+        if balance == 0 {
+            return err!(HolderMetadataPluginError::NotTokenHolder);
+        }
+
+        if !self.any_account {
+            let associated_token_account = get_associated_token_address_with_program_id(
+                &token_account.owner,
+                &token_account.mint,
+                token_account.to_account_info().owner,
+            );
+            if token_account.key() != associated_token_account {
+                return err!(HolderMetadataPluginError::TokenAccountNotAssociated);
+            }
+        }
+
+        Ok(balance)
+    }
+}
+
+/// Rules for the values holders write, since they end up in public metadata
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct HolderValueRules {
+    pub max_chars: Option<u32>,                // Maximum characters per value
+    pub allowed_chars: Option<Vec<CharClass>>, // Allowed characters, None for any
+    pub banned_substrings: Vec<String>,        // Rejected anywhere in a value, ignoring case
+    pub unique: bool,                          // Each value can only be claimed by one mint
+}
+
+impl HolderValueRules {
+    pub fn check_value(&self, val: &str) -> Result<()> {
+        if let Some(max_chars) = self.max_chars {
+            if val.chars().count() > max_chars as usize {
+                return err!(HolderMetadataPluginError::ValueTooLong);
+            }
+        }
+        if let Some(allowed_chars) = &self.allowed_chars {
+            if !val
+                .chars()
+                .all(|c| allowed_chars.iter().any(|class| class.contains(c)))
+            {
+                return err!(HolderMetadataPluginError::DisallowedCharacter);
+            }
+        }
+        let val = val.to_lowercase();
+        if self
+            .banned_substrings
+            .iter()
+            .any(|banned| !banned.is_empty() && val.contains(&banned.to_lowercase()))
+        {
+            return err!(HolderMetadataPluginError::BannedSubstring);
+        }
+        Ok(())
+    }
+}
+
+/// Who may edit as a holder
+#[derive(Clone, Debug, Default, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct HolderEligibility {
+    pub token_accounts: HolderTokenAccounts, // Token accounts that prove holding
+    pub min_balance: u64,                    // Minimum balance to edit, 0 for any
+    pub top_holder_only: bool,               // Only a larger balance overrides the last edit
+    pub group: Option<Pubkey>,               // Members of this group may edit its metadata instead
+}
+
+impl HolderEligibility {
+    /// Returns the holder's balance
+    pub fn check_holder(
+        &self,
+        token_account: &InterfaceAccount<TokenAccount>,
+        holder: &Pubkey,
+    ) -> Result<u64> {
+        let balance = self
+            .token_accounts
+            .check_token_account(token_account, holder)?;
+        if balance < self.min_balance {
+            return err!(HolderMetadataPluginError::BalanceTooLow);
+        }
+        Ok(balance)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct HolderPolicyConfig {
+    pub fields: HolderFields,       // Fields holders may edit
+    pub values: HolderValueRules,   // Rules for the values written
+    pub holders: HolderEligibility, // Who may edit as a holder
+    pub fee: Option<HolderFee>,     // Fee per edit
+    pub treasury: Pubkey,           // Receives the fees
+    pub cooldown_seconds: i64,      // Minimum time between edits, 0 for none
+    pub clear_on_transfer: bool,    // Transfer hook resets listed fields
+    pub moderator: Option<Pubkey>,  // Can also reset fields and ban holders
+}
+
+impl HolderPolicyConfig {
+    /// Checks the config before it's written to a policy
+    pub fn validate(&self) -> Result<()> {
+        if self.cooldown_seconds < 0 {
+            return err!(HolderMetadataPluginError::InvalidCooldown);
+        }
+        // The transfer hook only knows which fields to clear from the list
+        if self.clear_on_transfer && self.fields == HolderFields::AllKeys {
+            return err!(HolderMetadataPluginError::ClearOnTransferNeedsListedFields);
+        }
+        Ok(())
+    }
+
+    pub fn check_update(&self, field: &AnchorField, val: &str) -> Result<()> {
+        self.fields.check_field(field)?;
+        self.values.check_value(val)
+    }
+}
+
+/// Per-collection rules for holder edits, owned by the collection's update authority. The
+/// policy signer PDA is the field authority collections grant instead of the global PDA.
+#[account]
+pub struct HolderPolicy {
+    pub update_authority: Pubkey, // 32
+    pub signer_bump: u8,          // 1
+    pub config: HolderPolicyConfig,
+}
+
+impl HolderPolicy {
+    pub fn space(config: &HolderPolicyConfig) -> usize {
+        8 + 32 + 1 + config.try_to_vec().unwrap().len() // Extra 8 bytes for account discriminator
+    }
+
+    /// Checks the signer is the policy's moderator or update authority
+    pub fn check_moderator(&self, moderator: &Signer) -> Result<()> {
+        let key = moderator.key();
+        if key != self.update_authority && Some(key) != self.config.moderator {
+            return err!(HolderMetadataPluginError::NotModerator);
+        }
+        Ok(())
+    }
+}
+
+/// Edit state of a mint under a policy, shared by all of the policy's fields. The cooldown
+/// therefore runs from the last edit of any field, not per field.
+#[account]
+pub struct HolderFieldState {
+    pub last_update_ts: i64,              // 8
+    pub top_holder_token_account: Pubkey, // 32, default until a top holder edits
+}
+
+impl HolderFieldState {
+    pub const LEN: usize = 8 + 32 + 8; // Extra 8 bytes for account discriminator
+}
+
+/// Lets an ephemeral key edit fields for a holder until it expires. The holder must still hold
+/// the token when the key edits. Instructions holders sign take it as an optional
+/// `holder_session` account along with the `session_key` signer, only needed when a session key
+/// signs instead of the holder.
+#[account]
+pub struct HolderSession {
+    pub holder: Pubkey,      // 32
+    pub session_key: Pubkey, // 32
+    pub expires_at: i64,     // 8
+    pub fields: HolderFields,
+}
+
+impl HolderSession {
+    pub fn space(fields: &HolderFields) -> usize {
+        // Extra 8 bytes for account discriminator
+        8 + 32 + 32 + 8 + fields.try_to_vec().unwrap().len()
+    }
+}
+
+/// Blocks edits through a policy for a mint or a wallet until a moderator lifts it
+#[account]
+pub struct HolderBan {
+    pub holder_policy: Pubkey, // 32
+    pub target: Pubkey,        // 32, banned mint or wallet
+}
+
+impl HolderBan {
+    pub const LEN: usize = 8 + 32 + 32; // Extra 8 bytes for account discriminator
+}
+
+/// Reserves a normalized value of a field for one mint of a policy, derived from
+/// `["holder-value-claim", policy, field seed, sha256(normalized value)]`
+#[account]
+pub struct HolderValueClaim {
+    pub holder_policy: Pubkey, // 32
+    pub mint: Pubkey,          // 32
+    pub metadata: Pubkey,      // 32, read to release the claim once the mint's value changed
+}
+
+impl HolderValueClaim {
+    pub const LEN: usize = 8 + 32 + 32 + 32; // Extra 8 bytes for account discriminator
+}
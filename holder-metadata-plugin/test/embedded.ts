@@ -0,0 +1,156 @@
+import assert from "assert";
+
+import { describe, it } from "vitest";
+import {
+  PublicKey,
+  Keypair,
+  Transaction,
+  sendAndConfirmTransaction,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  LENGTH_SIZE,
+  TYPE_SIZE,
+  createAssociatedTokenAccount,
+  createInitializeMetadataPointerInstruction,
+  createInitializeMintInstruction,
+  getMintLen,
+  mintTo,
+} from "@solana/spl-token";
+import {
+  TokenMetadata,
+  Field,
+  createInitializeInstruction,
+  pack,
+} from "@solana/spl-token-metadata";
+import { AnchorError, workspace } from "@coral-xyz/anchor";
+
+import { ANCHOR_WALLET_KEYPAIR } from "../../test/constants";
+import { HOLDER_METADATA_PDA_SEED } from "../js";
+import { fieldToAnchorParam, getAccountMetadata } from "../../common/js";
+import { getConnection, setPayer } from "../../test/config";
+import { HolderMetadataPlugin } from "../../target/types/holder_metadata_plugin";
+
+describe("Holder Metadata Plugin with embedded metadata", () => {
+  const { program } = setPayer<HolderMetadataPlugin>(
+    ANCHOR_WALLET_KEYPAIR,
+    workspace.HolderMetadataPlugin
+  );
+
+  const [holderMetadataPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from(HOLDER_METADATA_PDA_SEED)],
+    program.programId
+  );
+
+  const HOLDER_FIELD_KEY = "nickname";
+
+  let mint: PublicKey;
+  let token: PublicKey;
+  let metadataVals: TokenMetadata;
+
+  it("Setup mint storing its metadata, and token", async () => {
+    const mintKeypair = Keypair.generate();
+    mint = mintKeypair.publicKey;
+    // The plugin's PDA is the update authority of the embedded metadata
+    metadataVals = {
+      name: "My test token",
+      symbol: "TEST",
+      uri: "http://test.test",
+      updateAuthority: holderMetadataPda,
+      mint,
+      additionalMetadata: [],
+    };
+
+    const space = getMintLen([ExtensionType.MetadataPointer]);
+    const metadataSpace = TYPE_SIZE + LENGTH_SIZE + pack(metadataVals).length;
+    const lamports = await getConnection().getMinimumBalanceForRentExemption(
+      space + metadataSpace
+    );
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        newAccountPubkey: mint,
+        space,
+        lamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeMetadataPointerInstruction(
+        mint,
+        null,
+        mint,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(
+        mint,
+        0,
+        ANCHOR_WALLET_KEYPAIR.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeInstruction({
+        programId: TOKEN_2022_PROGRAM_ID,
+        metadata: mint,
+        updateAuthority: holderMetadataPda,
+        mint,
+        mintAuthority: ANCHOR_WALLET_KEYPAIR.publicKey,
+        name: metadataVals.name,
+        symbol: metadataVals.symbol,
+        uri: metadataVals.uri,
+      })
+    );
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+      mintKeypair,
+    ]);
+
+    token = await createAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      mint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      mint,
+      token,
+      ANCHOR_WALLET_KEYPAIR,
+      1,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  function updateEmbedded(field: Field | string, val: string) {
+    return program.methods
+      .updateHolderFieldEmbedded(fieldToAnchorParam(field), val)
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint,
+        holderTokenAccount: token,
+        holderSession: null,
+        sessionKey: null,
+      })
+      .rpc();
+  }
+
+  it("Update holder field in the mint", async () => {
+    await updateEmbedded(HOLDER_FIELD_KEY, "Zorg");
+
+    metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, "Zorg"]];
+    const metadata = await getAccountMetadata(getConnection(), mint);
+    assert.deepStrictEqual(metadata, metadataVals);
+  });
+
+  it("Update name in the mint fails", async () => {
+    await assert.rejects(
+      updateEmbedded(Field.Name, "Zorg"),
+      (err: AnchorError) => err.error.errorCode.code === "FieldNotAllowed"
+    );
+  });
+});
@@ -0,0 +1,1083 @@
+import assert from "assert";
+
+import { describe, it } from "vitest";
+import {
+  PublicKey,
+  Keypair,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
+  SystemProgram,
+  LAMPORTS_PER_SOL,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  approve,
+  createAssociatedTokenAccount,
+  createInitializeGroupMemberPointerInstruction,
+  createInitializeGroupPointerInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferHookInstruction,
+  createMint,
+  getAccount,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  tokenGroupInitializeGroupWithRentTransfer,
+  tokenGroupMemberInitializeWithRentTransfer,
+  transferCheckedWithTransferHook,
+} from "@solana/spl-token";
+import {
+  TokenMetadata,
+  Field,
+  createInitializeInstruction,
+} from "@solana/spl-token-metadata";
+import { splDiscriminate } from "@solana/spl-type-length-value";
+import {
+  getArrayEncoder,
+  getStringEncoder,
+  getStructEncoder,
+  getU8Encoder,
+} from "@solana/codecs";
+import { AnchorError, BN, IdlTypes, workspace } from "@coral-xyz/anchor";
+
+import { ANCHOR_WALLET_KEYPAIR } from "../../test/constants";
+import { ATM_PROGRAM_ID } from "../../advanced-token-metadata/js";
+import {
+  getHolderBanPda,
+  getHolderPolicyPda,
+  getHolderPolicySignerPda,
+  getHolderValueClaimPda,
+  getHolderValueMint,
+} from "../js";
+import {
+  createInitializeFieldAuthoritiesIx,
+  FieldAuthorities,
+  getInstructionEncoder,
+  getSpaceRent,
+} from "../../field-authority-interface/js";
+import {
+  createMetadataAccount,
+  setupMintMetadataToken,
+} from "../../test/helpers";
+import {
+  getEmittedMetadata,
+  fieldToAnchorParam,
+  getReachMinRentTx,
+} from "../../common/js";
+import { getConnection, setPayer } from "../../test/config";
+import { HolderMetadataPlugin } from "../../target/types/holder_metadata_plugin";
+
+describe("Holder Metadata Plugin policies", () => {
+  const { program } = setPayer<HolderMetadataPlugin>(
+    ANCHOR_WALLET_KEYPAIR,
+    workspace.HolderMetadataPlugin
+  );
+
+  const updateAuthority = Keypair.generate();
+  const treasury = Keypair.generate();
+  const holderPolicy = getHolderPolicyPda(
+    updateAuthority.publicKey,
+    program.programId
+  );
+  const policySigner = getHolderPolicySignerPda(
+    holderPolicy,
+    program.programId
+  );
+
+  const NICKNAME_KEY = "nickname";
+  const FEE_LAMPORTS = 0.01 * LAMPORTS_PER_SOL;
+  const FEE_TOKENS = 5;
+  const BANNED_WORD = "heck";
+
+  let mint: PublicKey;
+  let metadata: PublicKey;
+  let token: PublicKey;
+
+  function getMetadataVals(mintPubkey: PublicKey): TokenMetadata {
+    return {
+      name: "My test token",
+      symbol: "TEST",
+      uri: "http://test.test",
+      updateAuthority: updateAuthority.publicKey,
+      mint: mintPubkey,
+      additionalMetadata: [],
+    };
+  }
+
+  type HolderFee = IdlTypes<HolderMetadataPlugin>["holderFee"];
+  type HolderTokenAccounts =
+    IdlTypes<HolderMetadataPlugin>["holderTokenAccounts"];
+  const lamportsFee: HolderFee = {
+    lamports: { amount: new BN(FEE_LAMPORTS) },
+  };
+  const ataOnly: HolderTokenAccounts = {
+    anyAccount: false,
+    delegates: false,
+    frozen: false,
+  };
+
+  function getPolicyConfig(
+    cooldownSeconds: number,
+    fee: HolderFee = lamportsFee,
+    tokenAccounts: HolderTokenAccounts = ataOnly
+  ) {
+    return {
+      fields: { only: [[fieldToAnchorParam(NICKNAME_KEY)]] },
+      values: {
+        maxChars: 10,
+        allowedChars: [{ letter: {} }, { number: {} }, { space: {} }],
+        bannedSubstrings: [BANNED_WORD],
+        unique: false,
+      },
+      holders: {
+        tokenAccounts,
+        minBalance: new BN(0),
+        topHolderOnly: false,
+        group: null as PublicKey | null,
+      },
+      fee,
+      treasury: treasury.publicKey,
+      cooldownSeconds: new BN(cooldownSeconds),
+      clearOnTransfer: false,
+      moderator: null as PublicKey | null,
+    };
+  }
+
+  // Default config with some of the holder rules overridden
+  function getPolicyConfigForHolders(
+    holders: Partial<ReturnType<typeof getPolicyConfig>["holders"]>
+  ) {
+    const config = getPolicyConfig(0);
+    return { ...config, holders: { ...config.holders, ...holders } };
+  }
+
+  const fieldAuthorities: FieldAuthorities = {
+    authorities: [{ field: NICKNAME_KEY, authority: policySigner }],
+  };
+
+  // Creates a mint and metadata whose nickname is delegated to the policy
+  async function setupHolderMint(): Promise<{
+    mint: PublicKey;
+    metadata: PublicKey;
+    token: PublicKey;
+  }> {
+    const mintKeypair = Keypair.generate();
+    const metadataKeypair = Keypair.generate();
+    const token = await setupMintMetadataToken(
+      mintKeypair,
+      metadataKeypair,
+      getMetadataVals(mintKeypair.publicKey),
+      fieldAuthorities
+    );
+    const mint = mintKeypair.publicKey;
+    const metadata = metadataKeypair.publicKey;
+
+    const tx = new Transaction().add(
+      createInitializeFieldAuthoritiesIx({
+        programId: ATM_PROGRAM_ID,
+        metadata,
+        updateAuthority: updateAuthority.publicKey,
+        fieldAuthorities,
+      })
+    );
+    const { rent } = await getSpaceRent(
+      getConnection(),
+      getMetadataVals(mint),
+      fieldAuthorities
+    );
+    const rentIx = await getReachMinRentTx(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      metadata,
+      rent
+    );
+    if (rentIx) {
+      tx.add(rentIx);
+    }
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+      updateAuthority,
+    ]);
+
+    return { mint, metadata, token };
+  }
+
+  it("Setup mint, metadata, token and field authorities", async () => {
+    const fundTx = new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        toPubkey: updateAuthority.publicKey,
+        lamports: LAMPORTS_PER_SOL,
+      }),
+      SystemProgram.transfer({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        toPubkey: treasury.publicKey,
+        lamports: LAMPORTS_PER_SOL,
+      })
+    );
+    await sendAndConfirmTransaction(getConnection(), fundTx, [
+      ANCHOR_WALLET_KEYPAIR,
+    ]);
+
+    ({ mint, metadata, token } = await setupHolderMint());
+  });
+
+  it("Create holder policy", async () => {
+    await program.methods
+      .createHolderPolicy(getPolicyConfig(0))
+      .accountsPartial({
+        updateAuthority: updateAuthority.publicKey,
+        holderPolicy,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    const policy = await program.account.holderPolicy.fetch(holderPolicy);
+    assert(policy.updateAuthority.equals(updateAuthority.publicKey));
+    assert(policy.config.treasury.equals(treasury.publicKey));
+  });
+
+  async function updateWithPolicy(
+    field: Field | string,
+    val: string,
+    accounts: Record<string, PublicKey | null> = {},
+    signers: Keypair[] = []
+  ): Promise<void> {
+    // The ban on the token account's owner is derived from its data
+    const { owner } = await getAccount(
+      getConnection(),
+      accounts.holderTokenAccount ?? token,
+      undefined,
+      accounts.tokenProgram ?? TOKEN_PROGRAM_ID
+    );
+    await program.methods
+      .updateHolderFieldWithPolicy(fieldToAnchorParam(field), val)
+      .accountsPartial({
+        ownerBan: getHolderBanPda(holderPolicy, owner, program.programId),
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint,
+        metadata,
+        holderTokenAccount: token,
+        holderPolicy,
+        fieldPda: null,
+        topHolderTokenAccount: null,
+        treasury: treasury.publicKey,
+        feeMint: null,
+        payerFeeTokenAccount: null,
+        treasuryFeeTokenAccount: null,
+        feeTokenProgram: null,
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
+        valueClaim: null,
+        previousValueClaim: null,
+        claimedMetadata: null,
+        ...accounts,
+      })
+      .signers(signers)
+      .rpc();
+  }
+
+  async function updatePolicy(
+    config: ReturnType<typeof getPolicyConfig>
+  ): Promise<void> {
+    await program.methods
+      .updateHolderPolicy(config)
+      .accountsPartial({
+        updateAuthority: updateAuthority.publicKey,
+        holderPolicy,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  }
+
+  it("Update allowed field charges the fee", async () => {
+    const balanceBefore = await getConnection().getBalance(treasury.publicKey);
+    await updateWithPolicy(NICKNAME_KEY, "Zorg");
+    const balanceAfter = await getConnection().getBalance(treasury.publicKey);
+    assert.equal(balanceAfter - balanceBefore, FEE_LAMPORTS);
+
+    const metadataVals = getMetadataVals(mint);
+    metadataVals.additionalMetadata = [[NICKNAME_KEY, "Zorg"]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  });
+
+  it("Update field outside the policy fails", async () => {
+    await assert.rejects(
+      updateWithPolicy(Field.Name, "Renamed"),
+      (err: AnchorError) => err.error.errorCode.code === "FieldNotAllowed"
+    );
+  });
+
+  it("Update with too long value fails", async () => {
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Zorg the Magnificent"),
+      (err: AnchorError) => err.error.errorCode.code === "ValueTooLong"
+    );
+  });
+
+  it("Update with disallowed character fails", async () => {
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Zorg!"),
+      (err: AnchorError) => err.error.errorCode.code === "DisallowedCharacter"
+    );
+  });
+
+  it("Update with banned word fails", async () => {
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Oh HECK"),
+      (err: AnchorError) => err.error.errorCode.code === "BannedSubstring"
+    );
+  });
+
+  it("Update as delegate only works when the policy allows it", async () => {
+    const delegate = Keypair.generate();
+    await approve(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      token,
+      delegate.publicKey,
+      ANCHOR_WALLET_KEYPAIR,
+      1
+    );
+
+    const updateAsDelegate = () =>
+      updateWithPolicy(
+        NICKNAME_KEY,
+        "Glorp",
+        { holder: delegate.publicKey },
+        [delegate]
+      );
+
+    await assert.rejects(
+      updateAsDelegate(),
+      (err: AnchorError) => err.error.errorCode.code === "NotTokenHolder"
+    );
+
+    await updatePolicy(
+      getPolicyConfig(0, lamportsFee, { ...ataOnly, delegates: true })
+    );
+
+    await updateAsDelegate();
+
+    const metadataVals = getMetadataVals(mint);
+    metadataVals.additionalMetadata = [[NICKNAME_KEY, "Glorp"]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  });
+
+  it("Banned owner can't edit through a delegate", async () => {
+    const delegate = Keypair.generate();
+    await approve(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      token,
+      delegate.publicKey,
+      ANCHOR_WALLET_KEYPAIR,
+      1
+    );
+    const updateAsDelegate = () =>
+      updateWithPolicy(
+        NICKNAME_KEY,
+        "Glorp",
+        { holder: delegate.publicKey },
+        [delegate]
+      );
+
+    // The update authority moderates policies without a moderator
+    const owner = ANCHOR_WALLET_KEYPAIR.publicKey;
+    await program.methods
+      .banHolder(owner)
+      .accountsPartial({ moderator: updateAuthority.publicKey, holderPolicy })
+      .signers([updateAuthority])
+      .rpc();
+    await assert.rejects(
+      updateAsDelegate(),
+      (err: AnchorError) => err.error.errorCode.code === "HolderBanned"
+    );
+
+    await program.methods
+      .liftHolderBan()
+      .accountsPartial({
+        moderator: updateAuthority.publicKey,
+        holderPolicy,
+        holderBan: getHolderBanPda(holderPolicy, owner, program.programId),
+      })
+      .signers([updateAuthority])
+      .rpc();
+    await updateAsDelegate();
+  });
+
+  it("Update needs the minimum balance, then the top balance", async () => {
+    const rival = Keypair.generate();
+    const rivalToken = await createAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      mint,
+      rival.publicKey
+    );
+    const mintTokens = (dest: PublicKey, amount: number) =>
+      mintTo(
+        getConnection(),
+        ANCHOR_WALLET_KEYPAIR,
+        mint,
+        dest,
+        ANCHOR_WALLET_KEYPAIR,
+        amount
+      );
+    const updateAsRival = (topHolderTokenAccount: PublicKey | null) =>
+      updateWithPolicy(
+        NICKNAME_KEY,
+        "Rival",
+        {
+          holder: rival.publicKey,
+          holderTokenAccount: rivalToken,
+          topHolderTokenAccount,
+        },
+        [rival]
+      );
+
+    await updatePolicy(getPolicyConfigForHolders({ minBalance: new BN(2) }));
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Zorg"),
+      (err: AnchorError) => err.error.errorCode.code === "BalanceTooLow"
+    );
+
+    // Holder has 3 tokens, rival 2
+    await mintTokens(token, 2);
+    await mintTokens(rivalToken, 2);
+    await updatePolicy(
+      getPolicyConfigForHolders({ minBalance: new BN(2), topHolderOnly: true })
+    );
+    await updateWithPolicy(NICKNAME_KEY, "Zorg");
+
+    await assert.rejects(
+      updateAsRival(null),
+      (err: AnchorError) =>
+        err.error.errorCode.code === "TopHolderAccountMismatch"
+    );
+    await assert.rejects(
+      updateAsRival(token),
+      (err: AnchorError) => err.error.errorCode.code === "NotTopHolder"
+    );
+
+    // Rival now has 4 tokens
+    await mintTokens(rivalToken, 2);
+    await updateAsRival(token);
+
+    const metadataVals = getMetadataVals(mint);
+    metadataVals.additionalMetadata = [[NICKNAME_KEY, "Rival"]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  });
+
+  it("Update charges a Token-2022 fee", async () => {
+    const feeMint = await createMint(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      null,
+      0,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const payerFeeTokenAccount = await getOrCreateAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      feeMint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const treasuryFeeTokenAccount = await getOrCreateAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      feeMint,
+      treasury.publicKey,
+      undefined,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      feeMint,
+      payerFeeTokenAccount.address,
+      ANCHOR_WALLET_KEYPAIR,
+      FEE_TOKENS,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const tokenFee: HolderFee = {
+      token: { mint: feeMint, amount: new BN(FEE_TOKENS) },
+    };
+    await updatePolicy(getPolicyConfig(0, tokenFee));
+
+    // Fails without the fee accounts
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Blip"),
+      (err: AnchorError) => err.error.errorCode.code === "FeeAccountsMissing"
+    );
+
+    await updateWithPolicy(NICKNAME_KEY, "Blip", {
+      feeMint,
+      payerFeeTokenAccount: payerFeeTokenAccount.address,
+      treasuryFeeTokenAccount: treasuryFeeTokenAccount.address,
+      feeTokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+
+    const treasuryAccount = await getAccount(
+      getConnection(),
+      treasuryFeeTokenAccount.address,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(treasuryAccount.amount, BigInt(FEE_TOKENS));
+  });
+
+  it("Moderator resets fields and bans mints and holders", async () => {
+    const moderator = Keypair.generate();
+    const fundTx = new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        toPubkey: moderator.publicKey,
+        lamports: LAMPORTS_PER_SOL,
+      })
+    );
+    await sendAndConfirmTransaction(getConnection(), fundTx, [
+      ANCHOR_WALLET_KEYPAIR,
+    ]);
+    await updatePolicy({
+      ...getPolicyConfig(0),
+      moderator: moderator.publicKey,
+    });
+    await updateWithPolicy(NICKNAME_KEY, "Rude");
+
+    const moderate = (signer: Keypair) =>
+      program.methods
+        .moderateHolderField(fieldToAnchorParam(NICKNAME_KEY))
+        .accountsPartial({
+          moderator: signer.publicKey,
+          metadata,
+          holderPolicy,
+          fieldPda: null,
+          fieldAuthorityProgram: ATM_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    // Holders can't moderate
+    await assert.rejects(
+      moderate(ANCHOR_WALLET_KEYPAIR),
+      (err: AnchorError) => err.error.errorCode.code === "NotModerator"
+    );
+
+    await moderate(moderator);
+    const metadataVals = getMetadataVals(mint);
+    metadataVals.additionalMetadata = [[NICKNAME_KEY, ""]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+
+    // Ban the mint, then the holder's wallet
+    const bans: [PublicKey, string][] = [
+      [mint, "Nice"],
+      [ANCHOR_WALLET_KEYPAIR.publicKey, "Kind"],
+    ];
+    for (const [target, val] of bans) {
+      await program.methods
+        .banHolder(target)
+        .accountsPartial({ moderator: moderator.publicKey, holderPolicy })
+        .signers([moderator])
+        .rpc();
+      await assert.rejects(
+        updateWithPolicy(NICKNAME_KEY, "Rude"),
+        (err: AnchorError) => err.error.errorCode.code === "HolderBanned"
+      );
+
+      await program.methods
+        .liftHolderBan()
+        .accountsPartial({
+          moderator: moderator.publicKey,
+          holderPolicy,
+          holderBan: getHolderBanPda(holderPolicy, target, program.programId),
+        })
+        .signers([moderator])
+        .rpc();
+      await updateWithPolicy(NICKNAME_KEY, val);
+    }
+  });
+
+  // Creates a Token-2022 mint whose group or member pointer points to itself
+  async function createPointerMint(
+    extension: ExtensionType,
+    getPointerIx: (mint: PublicKey) => TransactionInstruction
+  ): Promise<PublicKey> {
+    const mintKeypair = Keypair.generate();
+    const space = getMintLen([extension]);
+    const lamports =
+      await getConnection().getMinimumBalanceForRentExemption(space);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        newAccountPubkey: mintKeypair.publicKey,
+        space,
+        lamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      getPointerIx(mintKeypair.publicKey),
+      createInitializeMintInstruction(
+        mintKeypair.publicKey,
+        0,
+        ANCHOR_WALLET_KEYPAIR.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+      mintKeypair,
+    ]);
+    return mintKeypair.publicKey;
+  }
+
+  it("Update shared metadata as a group member", async () => {
+    const group = await createPointerMint(ExtensionType.GroupPointer, (m) =>
+      createInitializeGroupPointerInstruction(
+        m,
+        ANCHOR_WALLET_KEYPAIR.publicKey,
+        m,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await tokenGroupInitializeGroupWithRentTransfer(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      group,
+      ANCHOR_WALLET_KEYPAIR,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      BigInt(10)
+    );
+
+    const memberMint = await createPointerMint(
+      ExtensionType.GroupMemberPointer,
+      (m) =>
+        createInitializeGroupMemberPointerInstruction(
+          m,
+          ANCHOR_WALLET_KEYPAIR.publicKey,
+          m,
+          TOKEN_2022_PROGRAM_ID
+        )
+    );
+    await tokenGroupMemberInitializeWithRentTransfer(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      memberMint,
+      ANCHOR_WALLET_KEYPAIR,
+      group,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    const memberToken = await createAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      memberMint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      memberMint,
+      memberToken,
+      ANCHOR_WALLET_KEYPAIR,
+      1,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // The group's own metadata, delegated to the policy like every mint's
+    const groupMetadataKeypair = Keypair.generate();
+    const groupMetadata = groupMetadataKeypair.publicKey;
+    await createMetadataAccount(
+      groupMetadataKeypair,
+      getMetadataVals(group),
+      fieldAuthorities
+    );
+    const groupMetadataTx = new Transaction().add(
+      createInitializeInstruction({
+        programId: ATM_PROGRAM_ID,
+        metadata: groupMetadata,
+        updateAuthority: updateAuthority.publicKey,
+        mint: group,
+        mintAuthority: ANCHOR_WALLET_KEYPAIR.publicKey,
+        name: getMetadataVals(group).name,
+        symbol: getMetadataVals(group).symbol,
+        uri: getMetadataVals(group).uri,
+      }),
+      createInitializeFieldAuthoritiesIx({
+        programId: ATM_PROGRAM_ID,
+        metadata: groupMetadata,
+        updateAuthority: updateAuthority.publicKey,
+        fieldAuthorities,
+      })
+    );
+    await sendAndConfirmTransaction(getConnection(), groupMetadataTx, [
+      ANCHOR_WALLET_KEYPAIR,
+      updateAuthority,
+    ]);
+
+    await updatePolicy(getPolicyConfigForHolders({ group }));
+    const memberAccounts = {
+      mint: memberMint,
+      holderTokenAccount: memberToken,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    };
+
+    // Mints outside the group can't edit its metadata
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Wall", { metadata: groupMetadata }),
+      (err: AnchorError) => err.error.errorCode.code === "NotGroupMember"
+    );
+
+    // Members can't write to another mint's metadata granting the policy
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Wall", memberAccounts),
+      (err: AnchorError) => err.error.errorCode.code === "NotGroupMetadata"
+    );
+
+    await updateWithPolicy(NICKNAME_KEY, "Wall", {
+      ...memberAccounts,
+      metadata: groupMetadata,
+    });
+
+    const metadataVals = getMetadataVals(group);
+    metadataVals.additionalMetadata = [[NICKNAME_KEY, "Wall"]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      groupMetadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  });
+
+  it("Unique values can only be claimed by one mint", async () => {
+    const config = getPolicyConfig(0);
+    await updatePolicy({
+      ...config,
+      values: { ...config.values, unique: true },
+    });
+    const claimPda = (val: string) =>
+      getHolderValueClaimPda(
+        holderPolicy,
+        NICKNAME_KEY,
+        val,
+        program.programId
+      );
+
+    // Claims are required once values are unique
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Ace"),
+      (err: AnchorError) => err.error.errorCode.code === "ValueClaimMismatch"
+    );
+
+    // The previous value was set before the policy, so it has no claim
+    await updateWithPolicy(NICKNAME_KEY, "Ace", {
+      valueClaim: claimPda("Ace"),
+      previousValueClaim: claimPda("Kind"),
+    });
+
+    const other = await setupHolderMint();
+    const otherAccounts = {
+      mint: other.mint,
+      metadata: other.metadata,
+      holderTokenAccount: other.token,
+    };
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, " ace", {
+        ...otherAccounts,
+        valueClaim: claimPda(" ace"),
+      }),
+      (err: AnchorError) => err.error.errorCode.code === "ValueTaken"
+    );
+
+    // Changing the value releases the old claim
+    await updateWithPolicy(NICKNAME_KEY, "Bee", {
+      valueClaim: claimPda("Bee"),
+      previousValueClaim: claimPda("Ace"),
+    });
+    assert.equal(await getConnection().getAccountInfo(claimPda("Ace")), null);
+
+    await updateWithPolicy(NICKNAME_KEY, "Ace", {
+      ...otherAccounts,
+      valueClaim: claimPda("Ace"),
+    });
+
+    const lookup = (val: string) =>
+      getHolderValueMint(
+        getConnection(),
+        holderPolicy,
+        NICKNAME_KEY,
+        val,
+        program.programId
+      );
+    assert((await lookup("bee"))?.equals(mint));
+    assert((await lookup("ACE"))?.equals(other.mint));
+    assert.equal(await lookup("Zorg"), null);
+  });
+
+  // Only allows integers for the key, `KeyValueType::Integer` is variant 1
+  function createSetIntegerKeySchemaIx(
+    metadataPubkey: PublicKey,
+    key: string
+  ): TransactionInstruction {
+    return new TransactionInstruction({
+      programId: ATM_PROGRAM_ID,
+      keys: [
+        { pubkey: metadataPubkey, isSigner: false, isWritable: true },
+        {
+          pubkey: updateAuthority.publicKey,
+          isSigner: true,
+          isWritable: false,
+        },
+      ],
+      data: Buffer.from(
+        getInstructionEncoder(
+          splDiscriminate("advanced_token_metadata:set_key_schema"),
+          getStructEncoder([
+            [
+              "entries",
+              getArrayEncoder(
+                getStructEncoder([
+                  ["key", getStringEncoder()],
+                  ["valueType", getU8Encoder()],
+                ])
+              ),
+            ],
+          ])
+        ).encode({ entries: [{ key, valueType: 1 }] })
+      ),
+    });
+  }
+
+  it("Transfer clears fields with a typed key schema", async () => {
+    const hookMint = await createPointerMint(ExtensionType.TransferHook, (m) =>
+      createInitializeTransferHookInstruction(
+        m,
+        ANCHOR_WALLET_KEYPAIR.publicKey,
+        program.programId,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    const hookToken = await createAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      hookMint,
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      hookMint,
+      hookToken,
+      ANCHOR_WALLET_KEYPAIR,
+      1,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // The nickname may only hold integers, so clearing it writes an empty
+    // value the schema has to accept
+    const hookMetadataKeypair = Keypair.generate();
+    const hookMetadata = hookMetadataKeypair.publicKey;
+    const metadataVals = getMetadataVals(hookMint);
+    await createMetadataAccount(
+      hookMetadataKeypair,
+      metadataVals,
+      fieldAuthorities
+    );
+    const metadataTx = new Transaction().add(
+      createInitializeInstruction({
+        programId: ATM_PROGRAM_ID,
+        metadata: hookMetadata,
+        updateAuthority: updateAuthority.publicKey,
+        mint: hookMint,
+        mintAuthority: ANCHOR_WALLET_KEYPAIR.publicKey,
+        name: metadataVals.name,
+        symbol: metadataVals.symbol,
+        uri: metadataVals.uri,
+      }),
+      createInitializeFieldAuthoritiesIx({
+        programId: ATM_PROGRAM_ID,
+        metadata: hookMetadata,
+        updateAuthority: updateAuthority.publicKey,
+        fieldAuthorities,
+      }),
+      // Rent for the schema and the nickname
+      SystemProgram.transfer({
+        fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+        toPubkey: hookMetadata,
+        lamports: 0.01 * LAMPORTS_PER_SOL,
+      }),
+      createSetIntegerKeySchemaIx(hookMetadata, NICKNAME_KEY)
+    );
+    await sendAndConfirmTransaction(getConnection(), metadataTx, [
+      ANCHOR_WALLET_KEYPAIR,
+      updateAuthority,
+    ]);
+
+    await updatePolicy({ ...getPolicyConfig(0), clearOnTransfer: true });
+    await updateWithPolicy(NICKNAME_KEY, "42", {
+      mint: hookMint,
+      metadata: hookMetadata,
+      holderTokenAccount: hookToken,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+
+    await program.methods
+      .initializeHolderTransferHook()
+      .accountsPartial({
+        payer: ANCHOR_WALLET_KEYPAIR.publicKey,
+        updateAuthority: updateAuthority.publicKey,
+        holderPolicy,
+        mint: hookMint,
+        metadata: hookMetadata,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    const newOwner = Keypair.generate().publicKey;
+    const newOwnerToken = await createAssociatedTokenAccount(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      hookMint,
+      newOwner,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferCheckedWithTransferHook(
+      getConnection(),
+      ANCHOR_WALLET_KEYPAIR,
+      hookToken,
+      hookMint,
+      newOwnerToken,
+      ANCHOR_WALLET_KEYPAIR,
+      BigInt(1),
+      0,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      hookMetadata,
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata.additionalMetadata, [
+      [NICKNAME_KEY, ""],
+    ]);
+
+    // Fields added to the policy later need the extra accounts rewritten
+    await updatePolicy({
+      ...getPolicyConfig(0),
+      fields: {
+        only: [[fieldToAnchorParam(NICKNAME_KEY), fieldToAnchorParam("bio")]],
+      },
+      clearOnTransfer: true,
+    });
+    await program.methods
+      .updateHolderTransferHook()
+      .accountsPartial({
+        payer: ANCHOR_WALLET_KEYPAIR.publicKey,
+        updateAuthority: updateAuthority.publicKey,
+        holderPolicy,
+        mint: hookMint,
+        metadata: hookMetadata,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    // Discriminator, length and count, then 4 fixed accounts and 2 field PDAs
+    const extraAccountMetaList = PublicKey.findProgramAddressSync(
+      [Buffer.from("extra-account-metas"), hookMint.toBuffer()],
+      program.programId
+    )[0];
+    const extraAccountMetaListInfo = await getConnection().getAccountInfo(
+      extraAccountMetaList
+    );
+    assert.equal(extraAccountMetaListInfo?.data.length, 8 + 4 + 4 + 35 * 6);
+  });
+
+  it("Update during cooldown fails", async () => {
+    await updatePolicy(getPolicyConfig(3600));
+
+    await assert.rejects(
+      updateWithPolicy(NICKNAME_KEY, "Blip"),
+      (err: AnchorError) => err.error.errorCode.code === "CooldownActive"
+    );
+  });
+
+  it("Negative cooldown is rejected", async () => {
+    await assert.rejects(
+      updatePolicy(getPolicyConfig(-1)),
+      (err: AnchorError) => err.error.errorCode.code === "InvalidCooldown"
+    );
+  });
+
+  it("Clearing all keys on transfer is rejected", async () => {
+    const config = {
+      ...getPolicyConfig(0),
+      fields: { allKeys: {} },
+      clearOnTransfer: true,
+    };
+    await assert.rejects(
+      program.methods
+        .updateHolderPolicy(config)
+        .accountsPartial({
+          updateAuthority: updateAuthority.publicKey,
+          holderPolicy,
+        })
+        .signers([updateAuthority])
+        .rpc(),
+      (err: AnchorError) =>
+        err.error.errorCode.code === "ClearOnTransferNeedsListedFields"
+    );
+  });
+});
@@ -10,9 +10,9 @@ import {
   LAMPORTS_PER_SOL,
 } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
-import { TokenMetadata, Field } from "@solana/spl-token-metadata";
+import { TokenMetadata } from "@solana/spl-token-metadata";
 import * as borsh from "@coral-xyz/borsh";
-import { workspace } from "@coral-xyz/anchor";
+import { AnchorError, workspace } from "@coral-xyz/anchor";
 
 import { ANCHOR_WALLET_KEYPAIR } from "../../test/constants";
 import { ATM_PROGRAM_ID } from "../../advanced-token-metadata/js";
@@ -45,6 +45,8 @@ describe("Holder Metadata Plugin", () => {
     ).program.programId
   );
 
+  const HOLDER_FIELD_KEY = "nickname";
+
   function getMetadataVals(mint: PublicKey): TokenMetadata {
     const metadataVals: TokenMetadata = {
       name: "My test token",
@@ -73,13 +75,13 @@ describe("Holder Metadata Plugin", () => {
     tokens.push(token);
   });
 
-  it("Add name as holder field", async () => {
+  it("Add nickname as holder field", async () => {
     const ix = createAddFieldAuthorityIx(
       ANCHOR_WALLET_KEYPAIR.publicKey,
       metadatas[0],
       ANCHOR_WALLET_KEYPAIR.publicKey,
       holderMetadataPda,
-      Field.Name,
+      HOLDER_FIELD_KEY,
       ATM_PROGRAM_ID
     );
 
@@ -93,7 +95,7 @@ describe("Holder Metadata Plugin", () => {
     const [pda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from(FIELD_AUTHORITY_PDA_SEED),
-        Buffer.from(fieldToSeedStr(Field.Name)),
+        Buffer.from(fieldToSeedStr(HOLDER_FIELD_KEY)),
         metadatas[0].toBuffer(),
       ],
       ATM_PROGRAM_ID
@@ -105,7 +107,7 @@ describe("Holder Metadata Plugin", () => {
     assert(holderMetadataPda.equals(authority));
   });
 
-  async function updateNameWithHolder(
+  async function updateNicknameWithHolder(
     mint: PublicKey,
     metadata: PublicKey,
     token: PublicKey,
@@ -117,12 +119,12 @@ describe("Holder Metadata Plugin", () => {
       workspace.HolderMetadataPlugin
     );
 
-    const param = fieldToAnchorParam(Field.Name);
+    const param = fieldToAnchorParam(HOLDER_FIELD_KEY);
 
     const [fieldPda] = PublicKey.findProgramAddressSync(
       [
         Buffer.from(FIELD_AUTHORITY_PDA_SEED),
-        Buffer.from(fieldToSeedStr(Field.Name)),
+        Buffer.from(fieldToSeedStr(HOLDER_FIELD_KEY)),
         metadata.toBuffer(),
       ],
       ATM_PROGRAM_ID
@@ -145,7 +147,7 @@ describe("Holder Metadata Plugin", () => {
 
     // Check emmitted metadata
     const metadataVals = getMetadataVals(mint);
-    metadataVals.name = val;
+    metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, val]];
     const emittedMetadata = await getEmittedMetadata(
       getConnection(),
       ATM_PROGRAM_ID,
@@ -155,9 +157,9 @@ describe("Holder Metadata Plugin", () => {
     assert.deepStrictEqual(emittedMetadata, metadataVals);
   }
 
-  it("Update name with holder metadata succeeds", async () => {
+  it("Update nickname with holder metadata succeeds", async () => {
     const index = 0;
-    await updateNameWithHolder(
+    await updateNicknameWithHolder(
       mints[index],
       metadatas[index],
       tokens[index],
@@ -165,9 +167,9 @@ describe("Holder Metadata Plugin", () => {
     );
   });
 
-  it("Update longer name with holder metadata succeeds", async () => {
+  it("Update longer nickname with holder metadata succeeds", async () => {
     const index = 0;
-    await updateNameWithHolder(
+    await updateNicknameWithHolder(
       mints[index],
       metadatas[index],
       tokens[index],
@@ -176,6 +178,107 @@ describe("Holder Metadata Plugin", () => {
     );
   });
 
+  async function updateNicknameUnified(
+    fieldPda: PublicKey | null,
+    val: string = randomStr(10)
+  ): Promise<void> {
+    const { program } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+
+    await program.methods
+      .updateHolderFieldUnified(fieldToAnchorParam(HOLDER_FIELD_KEY), val)
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint: mints[0],
+        metadata: metadatas[0],
+        holderTokenAccount: tokens[0],
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        fieldPda,
+        holderSession: null,
+        sessionKey: null,
+      })
+      .rpc();
+
+    // Check emmitted metadata
+    const metadataVals = getMetadataVals(mints[0]);
+    metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, val]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadatas[0],
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  }
+
+  it("Update nickname with unified instruction uses field PDA", async () => {
+    const [fieldPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(FIELD_AUTHORITY_PDA_SEED),
+        Buffer.from(fieldToSeedStr(HOLDER_FIELD_KEY)),
+        metadatas[0].toBuffer(),
+      ],
+      ATM_PROGRAM_ID
+    );
+    await updateNicknameUnified(fieldPda);
+  });
+
+  it("Update nickname with unified instruction needs field PDA", async () => {
+    await assert.rejects(
+      updateNicknameUnified(null),
+      (err: AnchorError) => err.error.errorCode.code === "FieldPdaMissing"
+    );
+  });
+
+  it("Update nickname with multi-field instruction succeeds", async () => {
+    const { program } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+    const [fieldPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(FIELD_AUTHORITY_PDA_SEED),
+        Buffer.from(fieldToSeedStr(HOLDER_FIELD_KEY)),
+        metadatas[0].toBuffer(),
+      ],
+      ATM_PROGRAM_ID
+    );
+
+    const val = randomStr(10);
+    await program.methods
+      .updateHolderFields([
+        { field: fieldToAnchorParam(HOLDER_FIELD_KEY), val },
+      ])
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint: mints[0],
+        metadata: metadatas[0],
+        holderTokenAccount: tokens[0],
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
+      })
+      // v1 field PDAs, one per update
+      .remainingAccounts([
+        { pubkey: fieldPda, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+
+    const metadataVals = getMetadataVals(mints[0]);
+    metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, val]];
+    const emittedMetadata = await getEmittedMetadata(
+      getConnection(),
+      ATM_PROGRAM_ID,
+      metadatas[0],
+      ANCHOR_WALLET_KEYPAIR.publicKey
+    );
+    assert.deepStrictEqual(emittedMetadata, metadataVals);
+  });
+
   it("Setup holder for fail test", async () => {
     const ix = SystemProgram.transfer({
       fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
@@ -192,10 +295,10 @@ describe("Holder Metadata Plugin", () => {
     assert(balance === 5 * LAMPORTS_PER_SOL);
   });
 
-  it("Update name with non-holder fails", async () => {
+  it("Update nickname with non-holder fails", async () => {
     const index = 0;
     assert.rejects(async () => {
-      await updateNameWithHolder(
+      await updateNicknameWithHolder(
         mints[index],
         metadatas[index],
         tokens[index],
@@ -220,9 +323,9 @@ describe("Holder Metadata Plugin", () => {
     tokens.push(token);
   });
 
-  it("Update name with wrong token fails", async () => {
+  it("Update nickname with wrong token fails", async () => {
     assert.rejects(async () => {
-      await updateNameWithHolder(
+      await updateNicknameWithHolder(
         mints[0],
         metadatas[0],
         tokens[1],
@@ -230,4 +333,17 @@ describe("Holder Metadata Plugin", () => {
       );
     });
   });
+
+  it("Update nickname with token of another collection fails", async () => {
+    // Holds a token of the second mint, but targets the metadata of the first
+    await assert.rejects(
+      updateNicknameWithHolder(
+        mints[1],
+        metadatas[0],
+        tokens[1],
+        ANCHOR_WALLET_KEYPAIR
+      ),
+      (err: AnchorError) => err.error.errorCode.code === "MetadataMintMismatch"
+    );
+  });
 });
@@ -11,11 +11,11 @@ import {
 } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { TokenMetadata, Field } from "@solana/spl-token-metadata";
-import { workspace } from "@coral-xyz/anchor";
+import { AnchorError, BN, workspace } from "@coral-xyz/anchor";
 
 import { ANCHOR_WALLET_KEYPAIR } from "../../test/constants";
 import { ATM_PROGRAM_ID } from "../../advanced-token-metadata/js";
-import { HOLDER_METADATA_PDA_SEED } from "../js";
+import { HOLDER_METADATA_PDA_SEED, getHolderSessionPda } from "../js";
 import {
   createAddFieldAuthorityV2Ix,
   createInitializeFieldAuthoritiesIx,
@@ -61,8 +61,10 @@ describe("Holder Metadata Plugin", () => {
     return metadataVals;
   }
 
+  const HOLDER_FIELD_KEY = "nickname";
+
   const fieldAuthority: FieldAuthority = {
-    field: Field.Name,
+    field: HOLDER_FIELD_KEY,
     authority: holderMetadataPda,
   };
   const fieldAuthorities: FieldAuthorities = {
@@ -140,7 +142,7 @@ describe("Holder Metadata Plugin", () => {
     assert.deepStrictEqual(accountFieldAuthorities, fieldAuthorities);
   });
 
-  it("Add name as holder field", async () => {
+  it("Add nickname as holder field", async () => {
     const ix = createAddFieldAuthorityV2Ix({
       programId: ATM_PROGRAM_ID,
       metadata: metadatas[0],
@@ -163,7 +165,7 @@ describe("Holder Metadata Plugin", () => {
     assert.deepStrictEqual(accountFieldAuthorities, fieldAuthorities);
   });
 
-  async function updateNameWithHolderV2(
+  async function updateNicknameWithHolderV2(
     mint: PublicKey,
     metadata: PublicKey,
     token: PublicKey,
@@ -175,23 +177,26 @@ describe("Holder Metadata Plugin", () => {
       workspace.HolderMetadataPlugin
     );
 
-    const param = fieldToAnchorParam(Field.Name);
+    const param = fieldToAnchorParam(HOLDER_FIELD_KEY);
 
     await program.methods
       .updateHolderFieldV2(param, val)
       // TODO: Fix holderTokenAccount error when using `accounts()`
       .accountsPartial({
+        holder: payer.publicKey,
         mint,
         metadata,
         holderTokenAccount: token,
         fieldAuthorityProgram: ATM_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
       })
       .rpc();
 
     // Check emmitted metadata
     const metadataVals = getMetadataVals(mint);
-    metadataVals.name = val;
+    metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, val]];
     const emittedMetadata = await getEmittedMetadata(
       getConnection(),
       ATM_PROGRAM_ID,
@@ -201,9 +206,9 @@ describe("Holder Metadata Plugin", () => {
     assert.deepStrictEqual(emittedMetadata, metadataVals);
   }
 
-  it("Update name with holder metadata succeeds", async () => {
+  it("Update nickname with holder metadata succeeds", async () => {
     const index = 0;
-    await updateNameWithHolderV2(
+    await updateNicknameWithHolderV2(
       mints[index],
       metadatas[index],
       tokens[index],
@@ -211,9 +216,9 @@ describe("Holder Metadata Plugin", () => {
     );
   });
 
-  it("Update longer name with holder metadata succeeds", async () => {
+  it("Update longer nickname with holder metadata succeeds", async () => {
     const index = 0;
-    await updateNameWithHolderV2(
+    await updateNicknameWithHolderV2(
       mints[index],
       metadatas[index],
       tokens[index],
@@ -222,6 +227,149 @@ describe("Holder Metadata Plugin", () => {
     );
   });
 
+  it("Update nickname with unified instruction succeeds", async () => {
+    const { program } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+
+    const val = randomStr(10);
+    await program.methods
+      .updateHolderFieldUnified(fieldToAnchorParam(HOLDER_FIELD_KEY), val)
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint: mints[0],
+        metadata: metadatas[0],
+        holderTokenAccount: tokens[0],
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        fieldPda: null,
+        holderSession: null,
+        sessionKey: null,
+      })
+      .rpc();
+
+    const accountMetadata = await getAccountMetadata(
+      getConnection(),
+      metadatas[0]
+    );
+    assert.deepStrictEqual(accountMetadata.additionalMetadata, [
+      [HOLDER_FIELD_KEY, val],
+    ]);
+  });
+
+  describe("Session keys", () => {
+    const sessionKey = Keypair.generate();
+    const holderSession = getHolderSessionPda(
+      ANCHOR_WALLET_KEYPAIR.publicKey,
+      sessionKey.publicKey,
+      setPayer<HolderMetadataPlugin>(
+        ANCHOR_WALLET_KEYPAIR,
+        workspace.HolderMetadataPlugin
+      ).program.programId
+    );
+
+    // The session key pays, so the holder doesn't sign
+    async function updateWithSessionKey(
+      field: Field | string,
+      val: string
+    ): Promise<void> {
+      const { program } = setPayer<HolderMetadataPlugin>(
+        sessionKey,
+        workspace.HolderMetadataPlugin
+      );
+      await program.methods
+        .updateHolderFieldV2(fieldToAnchorParam(field), val)
+        .accountsPartial({
+          holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+          mint: mints[0],
+          metadata: metadatas[0],
+          holderTokenAccount: tokens[0],
+          fieldAuthorityProgram: ATM_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          holderSession,
+          sessionKey: sessionKey.publicKey,
+        })
+        .rpc();
+    }
+
+    it("Create holder session", async () => {
+      const fundTx = new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+          toPubkey: sessionKey.publicKey,
+          lamports: LAMPORTS_PER_SOL,
+        })
+      );
+      await sendAndConfirmTransaction(getConnection(), fundTx, [
+        ANCHOR_WALLET_KEYPAIR,
+      ]);
+
+      const { program } = setPayer<HolderMetadataPlugin>(
+        ANCHOR_WALLET_KEYPAIR,
+        workspace.HolderMetadataPlugin
+      );
+      const expiresAt = Math.floor(Date.now() / 1000) + 3600;
+      await program.methods
+        .createHolderSession(
+          sessionKey.publicKey,
+          { only: [[fieldToAnchorParam(HOLDER_FIELD_KEY)]] },
+          new BN(expiresAt)
+        )
+        .accountsPartial({
+          holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+          holderSession,
+        })
+        .rpc();
+
+      const session = await program.account.holderSession.fetch(holderSession);
+      assert(session.holder.equals(ANCHOR_WALLET_KEYPAIR.publicKey));
+      assert(session.sessionKey.equals(sessionKey.publicKey));
+    });
+
+    it("Update nickname with session key succeeds", async () => {
+      const val = randomStr(10);
+      await updateWithSessionKey(HOLDER_FIELD_KEY, val);
+
+      const metadataVals = getMetadataVals(mints[0]);
+      metadataVals.additionalMetadata = [[HOLDER_FIELD_KEY, val]];
+      const emittedMetadata = await getEmittedMetadata(
+        getConnection(),
+        ATM_PROGRAM_ID,
+        metadatas[0],
+        ANCHOR_WALLET_KEYPAIR.publicKey
+      );
+      assert.deepStrictEqual(emittedMetadata, metadataVals);
+    });
+
+    it("Update field outside the session fails", async () => {
+      await assert.rejects(
+        updateWithSessionKey("title", randomStr(10)),
+        (err: AnchorError) => err.error.errorCode.code === "FieldNotAllowed"
+      );
+    });
+
+    it("Update with closed session fails", async () => {
+      const { program } = setPayer<HolderMetadataPlugin>(
+        ANCHOR_WALLET_KEYPAIR,
+        workspace.HolderMetadataPlugin
+      );
+      await program.methods
+        .closeHolderSession()
+        .accountsPartial({
+          holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+          holderSession,
+        })
+        .rpc();
+
+      await assert.rejects(
+        updateWithSessionKey(HOLDER_FIELD_KEY, randomStr(10)),
+        (err: AnchorError) =>
+          err.error.errorCode.code === "AccountNotInitialized"
+      );
+    });
+  });
+
   it("Setup holder for fail test", async () => {
     const ix = SystemProgram.transfer({
       fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
@@ -238,10 +386,10 @@ describe("Holder Metadata Plugin", () => {
     assert(balance === 5 * LAMPORTS_PER_SOL);
   });
 
-  it("Update name with non-holder fails", async () => {
+  it("Update nickname with non-holder fails", async () => {
     const index = 0;
     assert.rejects(async () => {
-      await updateNameWithHolderV2(
+      await updateNicknameWithHolderV2(
         mints[index],
         metadatas[index],
         tokens[index],
@@ -266,9 +414,9 @@ describe("Holder Metadata Plugin", () => {
     tokens.push(token);
   });
 
-  it("Update name with wrong token fails", async () => {
+  it("Update nickname with wrong token fails", async () => {
     assert.rejects(async () => {
-      await updateNameWithHolderV2(
+      await updateNicknameWithHolderV2(
         mints[0],
         metadatas[0],
         tokens[1],
@@ -277,9 +425,9 @@ describe("Holder Metadata Plugin", () => {
     });
   });
 
-  it("Update name with wrong metadata fails", async () => {
+  it("Update nickname with wrong metadata fails", async () => {
     assert.rejects(async () => {
-      await updateNameWithHolderV2(
+      await updateNicknameWithHolderV2(
         mints[0],
         metadatas[1],
         tokens[0],
@@ -288,9 +436,9 @@ describe("Holder Metadata Plugin", () => {
     });
   });
 
-  it("Update name with wrong metadata fails", async () => {
+  it("Update nickname with wrong metadata fails", async () => {
     assert.rejects(async () => {
-      await updateNameWithHolderV2(
+      await updateNicknameWithHolderV2(
         mints[1],
         metadatas[0],
         tokens[0],
@@ -298,4 +446,166 @@ describe("Holder Metadata Plugin", () => {
       );
     });
   });
+
+  it("Update nickname with token of another collection fails", async () => {
+    // Holds a token of the second mint, but targets the metadata of the first
+    await assert.rejects(
+      updateNicknameWithHolderV2(
+        mints[1],
+        metadatas[0],
+        tokens[1],
+        ANCHOR_WALLET_KEYPAIR
+      ),
+      (err: AnchorError) => err.error.errorCode.code === "MetadataMintMismatch"
+    );
+  });
+
+  it("Update name with holder metadata fails even if granted", async () => {
+    // Mistakenly grant the plugin the name
+    const ix = createAddFieldAuthorityV2Ix({
+      programId: ATM_PROGRAM_ID,
+      metadata: metadatas[0],
+      updateAuthority: ANCHOR_WALLET_KEYPAIR.publicKey,
+      fieldAuthority: { field: Field.Name, authority: holderMetadataPda },
+      idempotent: true,
+    });
+    // Fund the extra field authority
+    const rentIx = SystemProgram.transfer({
+      fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+      toPubkey: metadatas[0],
+      lamports: 0.01 * LAMPORTS_PER_SOL,
+    });
+    const tx = new Transaction().add(rentIx, ix);
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+    ]);
+
+    const { program } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+    await assert.rejects(
+      program.methods
+        .updateHolderFieldV2(fieldToAnchorParam(Field.Name), randomStr(10))
+        .accountsPartial({
+          holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+          mint: mints[0],
+          metadata: metadatas[0],
+          holderTokenAccount: tokens[0],
+          fieldAuthorityProgram: ATM_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          holderSession: null,
+          sessionKey: null,
+        })
+        .rpc(),
+      (err: AnchorError) => err.error.errorCode.code === "FieldNotAllowed"
+    );
+  });
+
+  async function removeNicknameWithHolder(payer: Keypair): Promise<void> {
+    const { program } = setPayer<HolderMetadataPlugin>(
+      payer,
+      workspace.HolderMetadataPlugin
+    );
+    await program.methods
+      .removeHolderField(fieldToAnchorParam(HOLDER_FIELD_KEY))
+      .accountsPartial({
+        holder: payer.publicKey,
+        mint: mints[0],
+        metadata: metadatas[0],
+        holderTokenAccount: tokens[0],
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
+      })
+      .rpc();
+  }
+
+  it("Remove nickname with non-holder fails", async () => {
+    await assert.rejects(removeNicknameWithHolder(testHolder));
+  });
+
+  it("Remove nickname with holder metadata refunds rent", async () => {
+    const metadataBefore = await getConnection().getAccountInfo(metadatas[0]);
+    await removeNicknameWithHolder(ANCHOR_WALLET_KEYPAIR);
+
+    const accountMetadata = await getAccountMetadata(
+      getConnection(),
+      metadatas[0]
+    );
+    assert.deepStrictEqual(accountMetadata.additionalMetadata, []);
+
+    // Only the rent freed by the smaller account is refunded
+    const metadataAccount = await getConnection().getAccountInfo(metadatas[0]);
+    const freedRent =
+      (await getConnection().getMinimumBalanceForRentExemption(
+        metadataBefore!.data.length
+      )) -
+      (await getConnection().getMinimumBalanceForRentExemption(
+        metadataAccount!.data.length
+      ));
+    assert.equal(
+      metadataAccount!.lamports,
+      metadataBefore!.lamports - freedRent
+    );
+  });
+
+  it("Remove missing nickname fails", async () => {
+    await assert.rejects(removeNicknameWithHolder(ANCHOR_WALLET_KEYPAIR));
+  });
+
+  it("Update several fields at once succeeds", async () => {
+    // Grant the plugin a second key
+    const bioKey = "bio";
+    const ix = createAddFieldAuthorityV2Ix({
+      programId: ATM_PROGRAM_ID,
+      metadata: metadatas[0],
+      updateAuthority: ANCHOR_WALLET_KEYPAIR.publicKey,
+      fieldAuthority: { field: bioKey, authority: holderMetadataPda },
+      idempotent: true,
+    });
+    // Fund the extra field authority
+    const rentIx = SystemProgram.transfer({
+      fromPubkey: ANCHOR_WALLET_KEYPAIR.publicKey,
+      toPubkey: metadatas[0],
+      lamports: 0.01 * LAMPORTS_PER_SOL,
+    });
+    const tx = new Transaction().add(rentIx, ix);
+    await sendAndConfirmTransaction(getConnection(), tx, [
+      ANCHOR_WALLET_KEYPAIR,
+    ]);
+
+    const { program } = setPayer<HolderMetadataPlugin>(
+      ANCHOR_WALLET_KEYPAIR,
+      workspace.HolderMetadataPlugin
+    );
+    const nickname = randomStr(10);
+    const bio = randomStr(100);
+    await program.methods
+      .updateHolderFields([
+        { field: fieldToAnchorParam(HOLDER_FIELD_KEY), val: nickname },
+        { field: fieldToAnchorParam(bioKey), val: bio },
+      ])
+      .accountsPartial({
+        holder: ANCHOR_WALLET_KEYPAIR.publicKey,
+        mint: mints[0],
+        metadata: metadatas[0],
+        holderTokenAccount: tokens[0],
+        fieldAuthorityProgram: ATM_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        holderSession: null,
+        sessionKey: null,
+      })
+      .rpc();
+
+    const accountMetadata = await getAccountMetadata(
+      getConnection(),
+      metadatas[0]
+    );
+    assert.deepStrictEqual(accountMetadata.additionalMetadata, [
+      [HOLDER_FIELD_KEY, nickname],
+      [bioKey, bio],
+    ]);
+  });
 });